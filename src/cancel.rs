@@ -0,0 +1,41 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Shared flag to stop a running solve from another thread. Clones share the same flag, so
+// keep one and hand the other to `SolverConfig::cancel_token`.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<Flag>);
+
+#[derive(Debug, Default)]
+struct Flag {
+    cancelled: AtomicBool,
+    parents: Vec<CancelToken>, // Cancelling any of them cancels this token too
+}
+
+impl CancelToken {
+    pub fn new() -> CancelToken {
+        CancelToken::default()
+    }
+
+    // A token that is also cancelled when this one is. Cancelling the child leaves this one
+    // alone, so a part of the work can be stopped without stopping the rest.
+    pub fn child(&self) -> CancelToken {
+        CancelToken::linked([self.clone()])
+    }
+
+    pub(crate) fn linked(parents: impl IntoIterator<Item = CancelToken>) -> CancelToken {
+        CancelToken(Arc::new(Flag {
+            cancelled: AtomicBool::new(false),
+            parents: parents.into_iter().collect(),
+        }))
+    }
+
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::Relaxed)
+            || self.0.parents.iter().any(CancelToken::is_cancelled)
+    }
+}
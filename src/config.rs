@@ -0,0 +1,173 @@
+use std::num::NonZero;
+use std::thread;
+use std::time::Duration;
+
+use crate::{CancelToken, Heuristic, Limit, PatternDatabase, SearchStats};
+
+// Which search `solve_with` runs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Algorithm {
+    // Best-first over every generated state. Fastest, but the open list and the visited set
+    // grow with the search.
+    #[default]
+    AStar,
+    // Iterative-deepening A*: repeated depth-first passes under a rising push bound. Only the
+    // current push sequence and a fixed-size transposition table are kept, at the price of
+    // searching the same states again on every pass.
+    IdaStar,
+    // Best-first by the estimate alone, ignoring the pushes made so far. Usually the quickest
+    // to find some solution, but often a long one.
+    GreedyBestFirst,
+    // A* backwards from the solved layout, pulling boxes until the initial layout shows up.
+    // Often quicker when the goal area is cramped and the start is roomy.
+    Reverse,
+    // Forward pushes and backward pulls at the same time, joined as soon as one side reaches
+    // a state the other has seen. Each side only has to cover about half the solution, but
+    // the result is not guaranteed to be push-optimal.
+    Bidirectional,
+    // A* split over `SolverConfig::threads`, each thread owning the states whose box layout
+    // hashes to it. Push-optimal with `Heuristic::Hungarian`, the moves may still differ
+    // between runs. The observer only receives `on_progress`, and `open_list_mb` is ignored.
+    // Ends in `Limit::Memory` once the threads together hold about 2^32 states.
+    ParallelAStar,
+}
+
+// Knobs for a single solve. Start from `SolverConfig::new()` and chain the setters.
+// Limits are off by default, set them to bound how long a hard level may run.
+#[derive(Clone, Debug)]
+pub struct SolverConfig {
+    pub(crate) algorithm: Algorithm,
+    pub(crate) heuristic: Heuristic,
+    pub(crate) max_nodes: Option<usize>,
+    pub(crate) max_duration: Option<Duration>,
+    pub(crate) max_memory_bytes: Option<usize>,
+    pub(crate) table_bytes: Option<usize>,
+    pub(crate) open_bytes: Option<usize>,
+    pub(crate) threads: Option<usize>,
+    pub(crate) cancel: Option<CancelToken>,
+    pub(crate) patterns: Option<PatternDatabase>,
+    pub(crate) progress_interval: usize,
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        Self {
+            algorithm: Algorithm::default(),
+            heuristic: Heuristic::default(),
+            max_nodes: None,
+            max_duration: None,
+            max_memory_bytes: None,
+            table_bytes: None,
+            open_bytes: None,
+            threads: None,
+            cancel: None,
+            patterns: None,
+            progress_interval: 10_000,
+        }
+    }
+}
+
+impl SolverConfig {
+    pub fn new() -> SolverConfig {
+        SolverConfig::default()
+    }
+
+    pub fn algorithm(mut self, algorithm: Algorithm) -> SolverConfig {
+        self.algorithm = algorithm;
+        self
+    }
+
+    // With `Heuristic::Hungarian`, `AStar`, `IdaStar`, `Reverse` and `ParallelAStar` return a
+    // solution with the fewest possible pushes. `GreedyBestFirst` and `Bidirectional` do not.
+    pub fn heuristic(mut self, heuristic: Heuristic) -> SolverConfig {
+        self.heuristic = heuristic;
+        self
+    }
+
+    // Stop after expanding this many states.
+    pub fn max_nodes(mut self, max_nodes: usize) -> SolverConfig {
+        self.max_nodes = Some(max_nodes);
+        self
+    }
+
+    pub fn max_duration(mut self, max_duration: Duration) -> SolverConfig {
+        self.max_duration = Some(max_duration);
+        self
+    }
+
+    // Compared against `SearchStats::memory_bytes`, an estimate of the open list and
+    // visited set, not the real heap usage of the process.
+    pub fn max_memory_bytes(mut self, max_memory_bytes: usize) -> SolverConfig {
+        self.max_memory_bytes = Some(max_memory_bytes);
+        self
+    }
+
+    // Keeps visited states in a table of this many MiB allocated up front instead of one
+    // that grows with the search. `Algorithm::IdaStar` always uses such a table, 32 MiB
+    // unless set here. Once full, new states overwrite the most expensive entry of
+    // their bucket, so some states get expanded more than once.
+    pub fn transposition_table_mb(mut self, mb: usize) -> SolverConfig {
+        self.table_bytes = Some(mb << 20);
+        self
+    }
+
+    // Caps the open list at roughly this many MiB. When it fills up, the worse half of the
+    // open states is dropped and the search goes on with the rest. Running out of states
+    // after that ends in `Limit::Memory`, since the dropped ones may have led to a solution.
+    pub fn open_list_mb(mut self, mb: usize) -> SolverConfig {
+        self.open_bytes = Some(mb << 20);
+        self
+    }
+
+    // How many threads `Algorithm::ParallelAStar` runs, one per core by default.
+    pub fn threads(mut self, threads: usize) -> SolverConfig {
+        self.threads = Some(threads.max(1));
+        self
+    }
+
+    // The search checks the token between expansions and returns `SolveOutcome::Cancelled`.
+    pub fn cancel_token(mut self, token: CancelToken) -> SolverConfig {
+        self.cancel = Some(token);
+        self
+    }
+
+    // Starts with the deadlocks `database` holds for this level and adds those the search
+    // learns. Without one, learned deadlocks are forgotten once the solve returns.
+    pub fn deadlock_patterns(mut self, database: PatternDatabase) -> SolverConfig {
+        self.patterns = Some(database);
+        self
+    }
+
+    // How many expansions pass between two `SearchObserver::on_progress` calls.
+    pub fn progress_interval(mut self, nodes: usize) -> SolverConfig {
+        self.progress_interval = nodes.max(1);
+        self
+    }
+
+    pub(crate) fn thread_count(&self) -> usize {
+        self.threads
+            .unwrap_or_else(|| thread::available_parallelism().map_or(1, NonZero::get))
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(CancelToken::is_cancelled)
+    }
+
+    pub(crate) fn exceeded(&self, stats: &SearchStats) -> Option<Limit> {
+        if self
+            .max_nodes
+            .is_some_and(|max| stats.nodes_expanded >= max)
+        {
+            Some(Limit::Nodes)
+        } else if self.max_duration.is_some_and(|max| stats.elapsed >= max) {
+            Some(Limit::Time)
+        } else if self
+            .max_memory_bytes
+            .is_some_and(|max| stats.memory_bytes >= max)
+        {
+            Some(Limit::Memory)
+        } else {
+            None
+        }
+    }
+}
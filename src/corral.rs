@@ -0,0 +1,208 @@
+use std::collections::VecDeque;
+
+use ahash::{AHashMap, AHashSet};
+
+use crate::grid::CellMap;
+use crate::patterns::Deadlock;
+use crate::{
+    BoolGrid, BoxOrGoal, DIRECTIONS, Level, Pos, get_normalized_player, is_free, mark_reachable,
+};
+
+// States the sub-search may visit before it gives up on proving a corral dead.
+const SUB_SEARCH_NODES: usize = 256;
+// Sub-search verdicts kept before the cache starts over.
+const MAX_CACHED: usize = 1 << 16;
+
+// What the corrals of a state allow.
+pub(crate) enum Corral {
+    None,
+    Dead(Deadlock), // A PI-corral that can never be opened nor settled
+    Pi(BoxOrGoal),  // Only the pushes of these barrier boxes need to be generated
+}
+
+// Finds corrals: regions of the level the player cannot reach, fenced off by boxes. A
+// PI-corral is one whose barrier boxes can only ever be pushed into it, by pushes the player
+// can make right away. Unless it is already settled one of those pushes has to come first, so
+// the others can wait.
+pub(crate) struct Corrals {
+    component: CellMap<u32>, // Corral number of each free unreachable square, 0 elsewhere
+    squares: Vec<Pos>,       // Squares of the corral being looked at
+    stack: Vec<Pos>,
+    reachable: BoolGrid,
+    norm_buffer: BoolGrid,
+    // Whether a barrier is dead, by normalized player and first square of the corral.
+    cache: AHashMap<(BoxOrGoal, Pos, Pos), bool>,
+}
+
+impl Corrals {
+    pub(crate) fn new(level: &Level) -> Corrals {
+        let grid = &level.grid;
+        Corrals {
+            component: grid.map(0),
+            squares: Vec::new(),
+            stack: Vec::new(),
+            reachable: grid.map(false),
+            norm_buffer: grid.map(false),
+            cache: AHashMap::new(),
+        }
+    }
+
+    // `reachable` holds the squares the player reaches from `player` without pushing.
+    pub(crate) fn analyze(
+        &mut self,
+        boxes: &BoxOrGoal,
+        player: Pos,
+        reachable: &BoolGrid,
+        level: &Level,
+    ) -> Corral {
+        let grid = &level.grid;
+        self.component.fill(0);
+        let mut best: Option<BoxOrGoal> = None;
+        let mut id = 0;
+
+        for start in 0..grid.len() as Pos {
+            if reachable[start] || self.component[start] != 0 || !is_free(start, boxes, grid) {
+                continue;
+            }
+            id += 1;
+            self.fill(start, id, boxes, level);
+
+            let barrier: BoxOrGoal = boxes
+                .iter()
+                .copied()
+                .filter(|&pos| {
+                    DIRECTIONS
+                        .iter()
+                        .any(|&(dr, dc, _)| self.component[grid.step(pos, dr, dc)] == id)
+                })
+                .collect();
+            if self.is_settled(id, &barrier, level) || !self.is_pi(id, &barrier, reachable, level) {
+                continue;
+            }
+            if self.is_dead(id, &barrier, player, level) {
+                return Corral::Dead(Deadlock {
+                    player: self.normalize(player, &barrier, level),
+                    boxes: barrier,
+                    corral: start,
+                });
+            }
+            if best.as_ref().is_none_or(|best| barrier.len() < best.len()) {
+                best = Some(barrier);
+            }
+        }
+        best.map_or(Corral::None, Corral::Pi)
+    }
+
+    // Marks the free squares connected to `start` as corral `id` and lists them in `squares`.
+    fn fill(&mut self, start: Pos, id: u32, boxes: &BoxOrGoal, level: &Level) {
+        let grid = &level.grid;
+        self.squares.clear();
+        self.stack.clear();
+        self.stack.push(start);
+        self.component[start] = id;
+
+        while let Some(pos) = self.stack.pop() {
+            self.squares.push(pos);
+            for &(dr, dc, _) in &DIRECTIONS {
+                let next = grid.step(pos, dr, dc);
+                if self.component[next] == 0 && is_free(next, boxes, grid) {
+                    self.component[next] = id;
+                    self.stack.push(next);
+                }
+            }
+        }
+    }
+
+    // Nothing left to do inside: the boxes are all on goals and so are the corral's goals.
+    fn is_settled(&self, id: u32, boxes: &BoxOrGoal, level: &Level) -> bool {
+        let goals = &level.goals;
+        boxes.iter().all(|pos| goals.contains(pos))
+            && goals
+                .iter()
+                .all(|&goal| self.component[goal] != id || boxes.contains(&goal))
+    }
+
+    // Every push of a barrier box that leads out of the corral is blocked for good, by walls,
+    // barrier boxes or the corral itself, and every push into it can be made right now.
+    fn is_pi(&self, id: u32, barrier: &BoxOrGoal, reachable: &BoolGrid, level: &Level) -> bool {
+        let grid = &level.grid;
+        let in_corral = |pos| self.component[pos] == id;
+        let fixed = |pos| grid.is_wall(pos) || barrier.contains(&pos);
+
+        barrier.iter().all(|&pos| {
+            DIRECTIONS.iter().all(|&(dr, dc, _)| {
+                let to = grid.step(pos, dr, dc);
+                let from = grid.step(pos, -dr, -dc);
+                if in_corral(to) {
+                    reachable[from] || fixed(from)
+                } else {
+                    fixed(to) || fixed(from) || in_corral(from)
+                }
+            })
+        })
+    }
+
+    // Searches the barrier boxes alone, every other box removed, for a way to let the player
+    // into the corral or to settle it. Removing boxes only helps, so failing proves the
+    // corral dead. Gives up, and calls it alive, past `SUB_SEARCH_NODES` states.
+    fn is_dead(&mut self, id: u32, barrier: &BoxOrGoal, player: Pos, level: &Level) -> bool {
+        let grid = &level.grid;
+        let norm_player = self.normalize(player, barrier, level);
+        let key = (barrier.clone(), norm_player, self.squares[0]);
+        if let Some(&dead) = self.cache.get(&key) {
+            return dead;
+        }
+
+        let mut seen = AHashSet::new();
+        let mut queue = VecDeque::new();
+        seen.insert((barrier.clone(), norm_player));
+        queue.push_back((barrier.clone(), player));
+        let mut dead = true;
+
+        while let Some((boxes, player)) = queue.pop_front() {
+            if seen.len() > SUB_SEARCH_NODES || self.is_settled(id, &boxes, level) {
+                dead = false;
+                break;
+            }
+            self.reachable.fill(false);
+            self.stack.clear();
+            mark_reachable(player, &boxes, grid, &mut self.reachable, &mut self.stack);
+            if self.squares.iter().any(|&pos| self.reachable[pos]) {
+                dead = false;
+                break;
+            }
+
+            for (i, &pos) in boxes.iter().enumerate() {
+                for &(dr, dc, _) in &DIRECTIONS {
+                    let to = grid.step(pos, dr, dc);
+                    if !self.reachable[grid.step(pos, -dr, -dc)] || !is_free(to, &boxes, grid) {
+                        continue;
+                    }
+                    let mut new_boxes = boxes.clone();
+                    new_boxes[i] = to;
+                    new_boxes.sort_unstable();
+                    let norm_player = self.normalize(pos, &new_boxes, level);
+                    if seen.insert((new_boxes.clone(), norm_player)) {
+                        queue.push_back((new_boxes, pos));
+                    }
+                }
+            }
+        }
+
+        if self.cache.len() >= MAX_CACHED {
+            self.cache.clear();
+        }
+        self.cache.insert(key, dead);
+        dead
+    }
+
+    fn normalize(&mut self, player: Pos, boxes: &BoxOrGoal, level: &Level) -> Pos {
+        get_normalized_player(
+            player,
+            boxes,
+            &level.grid,
+            &mut self.norm_buffer,
+            &mut self.stack,
+        )
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::VecDeque;
+
+use crate::grid::{CellMap, Grid};
+use crate::{DIRECTIONS, DistanceMap, Pos};
+
+// Labels every floor-to-floor step with the biconnected block it belongs to, one label per
+// direction in `DIRECTIONS` order. Two neighbours of a cell stay connected when that cell is
+// blocked exactly when the steps towards them carry the same label.
+pub(crate) type SideBlocks = CellMap<[u32; 4]>;
+
+const NO_BLOCK: u32 = u32::MAX;
+
+// `DIRECTIONS` lists D, U, R, L, so flipping the lowest bit gives the opposite direction.
+fn opposite(dir: usize) -> usize {
+    dir ^ 1
+}
+
+// Iterative Tarjan: the recursion depth of the textbook version would follow the floor size.
+pub(crate) fn side_blocks(grid: &Grid) -> SideBlocks {
+    let mut blocks = grid.map([NO_BLOCK; 4]);
+    let mut discovered = grid.map(0u32);
+    let mut low = grid.map(0u32);
+    let mut time = 0;
+    let mut next_block = 0;
+
+    let mut frames: Vec<(Pos, usize)> = Vec::new(); // (cell, next direction to try)
+    let mut edges: Vec<(Pos, usize)> = Vec::new();
+
+    for root in 0..grid.len() as Pos {
+        if grid.is_wall(root) || discovered[root] != 0 {
+            continue;
+        }
+        time += 1;
+        discovered[root] = time;
+        low[root] = time;
+        frames.push((root, 0));
+
+        while let Some(&(cell, dir)) = frames.last() {
+            let parent = frames.len().checked_sub(2).map(|i| frames[i].0);
+
+            if dir < DIRECTIONS.len() {
+                frames.last_mut().unwrap().1 += 1;
+                let (dr, dc, _) = DIRECTIONS[dir];
+                let next = grid.step(cell, dr, dc);
+                if grid.is_wall(next) || Some(next) == parent {
+                    continue;
+                }
+
+                if discovered[next] == 0 {
+                    edges.push((cell, dir));
+                    time += 1;
+                    discovered[next] = time;
+                    low[next] = time;
+                    frames.push((next, 0));
+                } else if discovered[next] < discovered[cell] {
+                    edges.push((cell, dir));
+                    low[cell] = low[cell].min(discovered[next]);
+                }
+                continue;
+            }
+
+            frames.pop();
+            let Some(parent) = parent else {
+                continue;
+            };
+            low[parent] = low[parent].min(low[cell]);
+
+            // `parent` separates everything explored below `cell` from the rest: one block.
+            if low[cell] >= discovered[parent] {
+                while let Some((from, dir)) = edges.pop() {
+                    let (dr, dc, _) = DIRECTIONS[dir];
+                    let to = grid.step(from, dr, dc);
+                    blocks[from][dir] = next_block;
+                    blocks[to][opposite(dir)] = next_block;
+                    if from == parent && to == cell {
+                        break;
+                    }
+                }
+                next_block += 1;
+            }
+        }
+    }
+    blocks
+}
+
+// Minimum number of pushes to bring a lone box from each cell to `goal`.
+// Unlike a plain floor BFS this requires the player to reach the square behind the box,
+// so cells where the box could only get to the goal "geometrically" stay at `u16::MAX`.
+// Computed backwards: start with the box on the goal and pull it with the player.
+pub(crate) fn compute_push_distance_map(
+    goal: Pos,
+    grid: &Grid,
+    blocks: &SideBlocks,
+) -> DistanceMap {
+    lone_box_distances(goal, grid, blocks, true)
+}
+
+// Minimum number of pulls to bring a lone box from each cell to `start`, what the reverse
+// search needs to head back to the initial layout. Computed forwards: push the box away.
+pub(crate) fn compute_pull_distance_map(
+    start: Pos,
+    grid: &Grid,
+    blocks: &SideBlocks,
+) -> DistanceMap {
+    lone_box_distances(start, grid, blocks, false)
+}
+
+// Breadth-first search from a box on `from`, moving it by pulls or by pushes.
+fn lone_box_distances(from: Pos, grid: &Grid, blocks: &SideBlocks, pull: bool) -> DistanceMap {
+    // Distance per (box cell, side of the box the player stands on).
+    let mut side_dist = grid.map([u16::MAX; 4]);
+    let mut queue: VecDeque<(Pos, usize)> = VecDeque::new();
+
+    for side in 0..DIRECTIONS.len() {
+        if blocks[from][side] != NO_BLOCK && side_dist[from][side] == u16::MAX {
+            reach_sides(&mut side_dist, &mut queue, blocks, from, side, 0);
+        }
+    }
+
+    while let Some((cell, side)) = queue.pop_front() {
+        let dist = side_dist[cell][side];
+        if dist == u16::MAX - 1 {
+            continue;
+        }
+
+        let (dr, dc, _) = DIRECTIONS[side];
+        let next = if pull {
+            // Pull: the box moves onto the player's square and the player steps back once more.
+            let player = grid.step(cell, dr, dc);
+            if grid.is_wall(grid.step(player, dr, dc)) {
+                continue;
+            }
+            player
+        } else {
+            // Push: the box moves away from the player, who takes its square.
+            grid.step(cell, -dr, -dc)
+        };
+        if grid.is_wall(next) || side_dist[next][side] != u16::MAX {
+            continue;
+        }
+        reach_sides(&mut side_dist, &mut queue, blocks, next, side, dist + 1);
+    }
+
+    let mut dist_map = grid.map(u16::MAX);
+    for pos in 0..grid.len() as Pos {
+        dist_map[pos] = side_dist[pos].iter().copied().min().unwrap_or(u16::MAX);
+    }
+    // The box is already there, even if the player has no side to stand on.
+    dist_map[from] = 0;
+    dist_map
+}
+
+// The player walks around the box for free, so every side in the same block is reached at once.
+fn reach_sides(
+    side_dist: &mut CellMap<[u16; 4]>,
+    queue: &mut VecDeque<(Pos, usize)>,
+    blocks: &SideBlocks,
+    cell: Pos,
+    side: usize,
+    dist: u16,
+) {
+    let block = blocks[cell][side];
+    for other in 0..DIRECTIONS.len() {
+        if blocks[cell][other] == block && side_dist[cell][other] == u16::MAX {
+            side_dist[cell][other] = dist;
+            queue.push_back((cell, other));
+        }
+    }
+}
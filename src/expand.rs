@@ -0,0 +1,179 @@
+use crate::corral::{Corral, Corrals};
+use crate::matching::GoalMatching;
+use crate::patterns::DeadlockPatterns;
+use crate::reverse::PullModel;
+use crate::{
+    BoolGrid, BoxOrGoal, DIRECTIONS, Level, Pos, SearchStats, SolverConfig, is_free,
+    is_freeze_deadlock, is_square_deadlock, mark_reachable,
+};
+
+// Which way a search moves the boxes.
+pub(crate) enum Moves {
+    Push,
+    // Backwards from the solved layout. Done once the initial boxes show up with the player
+    // in the region normalized to `target_player`.
+    Pull {
+        model: PullModel,
+        target_player: Pos,
+    },
+}
+
+// One box moved by one square, by a push or a pull along `DIRECTIONS[dir]`.
+pub(crate) struct BoxMove {
+    pub(crate) from: Pos,
+    pub(crate) to: Pos,
+    pub(crate) player: Pos, // Where the player ends up
+    pub(crate) dir: u8,
+}
+
+// Generates the children of a state and throws out those the deadlock checks prove
+// unsolvable. Every search keeps one, so they all prune the same way.
+pub(crate) struct Expander<'a> {
+    level: &'a Level,
+    pub(crate) moves: Moves,
+    corrals: Option<Corrals>, // Pushes only, pulls never fence the player out for good
+    patterns: Option<DeadlockPatterns>, // Pushes only, like `corrals`
+    matching: GoalMatching,
+    // Using in-place mutation avoids cloning and heap allocation, making the flood fill faster.
+    reachable: BoolGrid,
+    stack: Vec<Pos>,
+}
+
+impl<'a> Expander<'a> {
+    pub(crate) fn new(level: &'a Level, config: &SolverConfig, moves: Moves) -> Expander<'a> {
+        let grid = &level.grid;
+        let pushes = matches!(moves, Moves::Push);
+        Expander {
+            level,
+            corrals: pushes.then(|| Corrals::new(level)),
+            patterns: pushes.then(|| DeadlockPatterns::new(level, config.patterns.as_ref())),
+            moves,
+            matching: GoalMatching::new(),
+            reachable: grid.map(false),
+            stack: Vec::with_capacity(grid.len()),
+        }
+    }
+
+    // Hands every child of the state that survives the deadlock checks to `visit`, with the
+    // box move that leads to it. A state inside a dead corral has no children at all.
+    pub(crate) fn for_each_child<F>(
+        &mut self,
+        boxes: &BoxOrGoal,
+        player: Pos,
+        stats: &mut SearchStats,
+        mut visit: F,
+    ) where
+        F: FnMut(BoxOrGoal, BoxMove, &mut SearchStats),
+    {
+        let level = self.level;
+        let grid = &level.grid;
+
+        // Flood fill to find all squares the player can reach without moving a box.
+        self.reachable.fill(false);
+        self.stack.clear();
+        mark_reachable(player, boxes, grid, &mut self.reachable, &mut self.stack);
+
+        let mut barrier = None;
+        if let Some(corrals) = &mut self.corrals {
+            match corrals.analyze(boxes, player, &self.reachable, level) {
+                Corral::None => {}
+                Corral::Dead(deadlock) => {
+                    stats.deadlocks.corral += 1;
+                    if let Some(patterns) = &mut self.patterns {
+                        patterns.learn(deadlock, level);
+                    }
+                    return;
+                }
+                Corral::Pi(boxes) => barrier = Some(boxes),
+            }
+        }
+
+        for (i, &box_position) in boxes.iter().enumerate() {
+            if barrier.as_ref().is_some_and(|b| !b.contains(&box_position)) {
+                continue;
+            }
+            for (dir, &(dr, dc, _)) in DIRECTIONS.iter().enumerate() {
+                let (new_box_pos, new_player_pos) = match self.moves {
+                    Moves::Push => {
+                        let new_box_pos = grid.step(box_position, dr, dc);
+                        let behind = grid.step(box_position, -dr, -dc);
+                        if !self.reachable[behind] || !is_free(new_box_pos, boxes, grid) {
+                            continue;
+                        }
+                        (new_box_pos, box_position)
+                    }
+                    // The player stands next to the box on the side opposite to `dir` and walks
+                    // away from it, dragging the box along. Pushing it back along `dir` undoes it.
+                    Moves::Pull { .. } => {
+                        let new_box_pos = grid.step(box_position, -dr, -dc);
+                        let new_player_pos = grid.step(new_box_pos, -dr, -dc);
+                        if !self.reachable[new_box_pos] || !is_free(new_player_pos, boxes, grid) {
+                            continue;
+                        }
+                        (new_box_pos, new_player_pos)
+                    }
+                };
+
+                let dead = match &self.moves {
+                    Moves::Push => &level.dead,
+                    Moves::Pull { model, .. } => &model.dead,
+                };
+                if dead[new_box_pos] {
+                    stats.deadlocks.dead_square += 1;
+                    continue;
+                }
+
+                // Cloning `SmallVec` is very cheap.
+                let mut new_boxes = boxes.clone();
+                new_boxes[i] = new_box_pos;
+                new_boxes.sort_unstable();
+
+                // Freeze patterns only trap boxes that are pushed, a pull can always undo them.
+                if let Moves::Push = self.moves
+                    && self.is_push_deadlock(&new_boxes, box_position, new_box_pos, stats)
+                {
+                    continue;
+                }
+
+                let step = BoxMove {
+                    from: box_position,
+                    to: new_box_pos,
+                    player: new_player_pos,
+                    dir: dir as u8,
+                };
+                visit(new_boxes, step, stats);
+            }
+        }
+    }
+
+    // The checks for a box just pushed from `from` to `to`, cheapest first. Counts the one
+    // that fired in `stats`.
+    fn is_push_deadlock(
+        &mut self,
+        boxes: &BoxOrGoal,
+        from: Pos,
+        to: Pos,
+        stats: &mut SearchStats,
+    ) -> bool {
+        let level = self.level;
+        let deadlocks = &mut stats.deadlocks;
+        let formations = &level.formations;
+        let counter = if formations.overfills_segment(to, boxes) {
+            &mut deadlocks.segment
+        } else if formations.closes_diagonal(to, boxes, from, &level.goals) {
+            &mut deadlocks.diagonal
+        } else if is_freeze_deadlock(to, boxes, level) {
+            &mut deadlocks.frozen
+        } else if is_square_deadlock(to, boxes, &level.goals, &level.grid) {
+            &mut deadlocks.square
+        } else if (self.patterns.as_mut()).is_some_and(|p| p.is_dead(boxes, from, to, level)) {
+            &mut deadlocks.pattern
+        } else if !self.matching.has_matching(boxes, level) {
+            &mut deadlocks.bipartite
+        } else {
+            return false;
+        };
+        *counter += 1;
+        true
+    }
+}
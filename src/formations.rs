@@ -0,0 +1,147 @@
+use ahash::AHashSet;
+use smallvec::SmallVec;
+
+use crate::grid::{CellMap, Grid};
+use crate::{BoolGrid, BoxOrGoal, Pos};
+
+// A run of squares along a wall, closed off by walls at both ends. A box on it can only be
+// pushed along the wall, so the run never takes more boxes than it has goals.
+#[derive(Clone)]
+struct Segment {
+    first: Pos,
+    last: Pos,
+    step: Pos, // 1 along a row, the grid width along a column
+    goals: usize,
+}
+
+impl Segment {
+    fn contains(&self, pos: Pos) -> bool {
+        (self.first..=self.last).contains(&pos) && (pos - self.first).is_multiple_of(self.step)
+    }
+}
+
+// Deadlocks made of walls and at most a couple of boxes, enumerated once per level so the
+// push filter only has to look them up.
+#[derive(Clone)]
+pub(crate) struct Formations {
+    segments: Vec<Segment>, // Only those with some goals, but fewer than squares
+    segments_at: CellMap<SmallVec<[u32; 2]>>,
+    // Closed diagonals: walls on two sides of a dead corner, and on the far side of the two
+    // other neighbours. Boxes on both of those can only be pushed into the corner, unless the
+    // player is in it. By square of one box, the square of the other and the corner.
+    diagonals: CellMap<SmallVec<[(Pos, Pos); 2]>>,
+}
+
+impl Formations {
+    // Marks the squares of wall segments without goals in `dead`, then looks for closed
+    // diagonals around the dead squares.
+    pub(crate) fn new(grid: &Grid, goals: &BoxOrGoal, dead: &mut BoolGrid) -> Formations {
+        let mut formations = Formations {
+            segments: Vec::new(),
+            segments_at: grid.map(SmallVec::new()),
+            diagonals: grid.map(SmallVec::new()),
+        };
+        formations.find_segments(grid, goals, dead);
+        formations.find_diagonals(grid, dead);
+        formations
+    }
+
+    fn find_segments(&mut self, grid: &Grid, goals: &BoxOrGoal, dead: &mut BoolGrid) {
+        let mut seen = AHashSet::new();
+        let sides = [
+            ((0, 1), -1, 0),
+            ((0, 1), 1, 0),
+            ((1, 0), 0, -1),
+            ((1, 0), 0, 1),
+        ];
+        for ((dr, dc), side_dr, side_dc) in sides {
+            for first in 0..grid.len() as Pos {
+                if grid.is_wall(first) || !grid.is_wall(grid.step(first, -dr, -dc)) {
+                    continue;
+                }
+                let mut squares = vec![first];
+                let mut pos = first;
+                loop {
+                    if !grid.is_wall(grid.step(pos, side_dr, side_dc)) {
+                        squares.clear();
+                        break;
+                    }
+                    let next = grid.step(pos, dr, dc);
+                    if grid.is_wall(next) {
+                        break;
+                    }
+                    squares.push(next);
+                    pos = next;
+                }
+                // Open at the side somewhere, or already found along the opposite wall.
+                if squares.is_empty() || !seen.insert((first, pos)) {
+                    continue;
+                }
+
+                let on_goals = squares.iter().filter(|pos| goals.contains(pos)).count();
+                if on_goals == 0 {
+                    squares.iter().for_each(|&pos| dead[pos] = true);
+                } else if on_goals < squares.len() {
+                    let id = self.segments.len() as u32;
+                    squares
+                        .iter()
+                        .for_each(|&pos| self.segments_at[pos].push(id));
+                    self.segments.push(Segment {
+                        first,
+                        last: pos,
+                        step: grid.step(first, dr, dc) - first,
+                        goals: on_goals,
+                    });
+                }
+            }
+        }
+    }
+
+    fn find_diagonals(&mut self, grid: &Grid, dead: &BoolGrid) {
+        let corners = [(-1, 0, 0, -1), (-1, 0, 0, 1), (1, 0, 0, -1), (1, 0, 0, 1)];
+        for corner in 0..grid.len() as Pos {
+            if grid.is_wall(corner) || !dead[corner] {
+                continue;
+            }
+            for (ar, ac, br, bc) in corners {
+                let a = grid.step(corner, ar, ac);
+                let b = grid.step(corner, br, bc);
+                let walls = [
+                    grid.step(corner, -ar, -ac),
+                    grid.step(corner, -br, -bc),
+                    grid.step(a, -br, -bc),
+                    grid.step(b, -ar, -ac),
+                ];
+                if grid.is_wall(a) || grid.is_wall(b) || !walls.iter().all(|&w| grid.is_wall(w)) {
+                    continue;
+                }
+                self.diagonals[a].push((b, corner));
+                self.diagonals[b].push((a, corner));
+            }
+        }
+    }
+
+    // Whether the push onto `box_pos` put more boxes on one of its wall segments than there
+    // are goals.
+    pub(crate) fn overfills_segment(&self, box_pos: Pos, boxes: &BoxOrGoal) -> bool {
+        self.segments_at[box_pos].iter().any(|&id| {
+            let segment = &self.segments[id as usize];
+            boxes.iter().filter(|&&pos| segment.contains(pos)).count() > segment.goals
+        })
+    }
+
+    // Whether the push onto `box_pos` closed a diagonal with a box off its goal.
+    pub(crate) fn closes_diagonal(
+        &self,
+        box_pos: Pos,
+        boxes: &BoxOrGoal,
+        player: Pos,
+        goals: &BoxOrGoal,
+    ) -> bool {
+        self.diagonals[box_pos].iter().any(|&(other, corner)| {
+            boxes.contains(&other)
+                && player != corner
+                && !(goals.contains(&box_pos) && goals.contains(&other))
+        })
+    }
+}
@@ -0,0 +1,120 @@
+use std::ops::{Index, IndexMut};
+
+use crate::Pos;
+
+// A per-cell table sized to the level, indexed by `Pos`.
+#[derive(Clone)]
+pub(crate) struct CellMap<T> {
+    cells: Vec<T>,
+}
+
+impl<T: Clone> CellMap<T> {
+    pub(crate) fn fill(&mut self, value: T) {
+        self.cells.fill(value);
+    }
+}
+
+impl<T> CellMap<T> {
+    pub(crate) fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.cells.iter()
+    }
+}
+
+impl<T> Index<Pos> for CellMap<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &T {
+        &self.cells[pos as usize]
+    }
+}
+
+impl<T> IndexMut<Pos> for CellMap<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut T {
+        &mut self.cells[pos as usize]
+    }
+}
+
+// The level layout. Cells are numbered row by row (`row * width + col`), so a position is
+// a single index and every table only holds as many cells as the level really has.
+#[derive(Clone)]
+pub(crate) struct Grid {
+    width: usize,
+    height: usize,
+    cells: CellMap<u8>,
+}
+
+impl Grid {
+    pub(crate) fn new(width: usize, height: usize) -> Grid {
+        Grid {
+            width,
+            height,
+            cells: CellMap {
+                cells: vec![b' '; width * height],
+            },
+        }
+    }
+
+    pub(crate) fn width(&self) -> usize {
+        self.width
+    }
+
+    pub(crate) fn height(&self) -> usize {
+        self.height
+    }
+
+    // Number of cells, also an upper bound for any path length on this grid.
+    pub(crate) fn len(&self) -> usize {
+        self.width * self.height
+    }
+
+    // Allocates a table with one `value` per cell of this grid.
+    pub(crate) fn map<T: Clone>(&self, value: T) -> CellMap<T> {
+        CellMap {
+            cells: vec![value; self.len()],
+        }
+    }
+
+    pub(crate) fn pos(&self, row: usize, col: usize) -> Pos {
+        (row * self.width + col) as Pos
+    }
+
+    pub(crate) fn coords(&self, pos: Pos) -> (usize, usize) {
+        (pos as usize / self.width, pos as usize % self.width)
+    }
+
+    pub(crate) fn is_wall(&self, pos: Pos) -> bool {
+        self.cells[pos] == b'#'
+    }
+
+    // Moves one cell without bounds checks, the outer wall keeps the search inside the grid.
+    pub(crate) fn step(&self, pos: Pos, dr: i8, dc: i8) -> Pos {
+        let offset = dr as isize * self.width as isize + dc as isize;
+        (pos as isize + offset) as Pos
+    }
+
+    // Moves one cell, or returns `None` when that would leave the grid.
+    pub(crate) fn checked_step(&self, pos: Pos, dr: i8, dc: i8) -> Option<Pos> {
+        let (row, col) = self.coords(pos);
+        let row = row.checked_add_signed(dr as isize)?;
+        let col = col.checked_add_signed(dc as isize)?;
+        (row < self.height && col < self.width).then(|| self.pos(row, col))
+    }
+
+    pub(crate) fn rows(&self) -> impl Iterator<Item = &[u8]> {
+        self.cells.cells.chunks(self.width)
+    }
+}
+
+impl Index<Pos> for Grid {
+    type Output = u8;
+
+    fn index(&self, pos: Pos) -> &u8 {
+        &self.cells[pos]
+    }
+}
+
+impl IndexMut<Pos> for Grid {
+    fn index_mut(&mut self, pos: Pos) -> &mut u8 {
+        &mut self.cells[pos]
+    }
+}
@@ -0,0 +1,169 @@
+use smallvec::SmallVec;
+
+use crate::{BoxOrGoal, DistanceMap};
+
+// How the solver estimates the remaining number of pushes of a state.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Heuristic {
+    // Cheapest box/goal pairs first. Fast, but it can overestimate, so solutions are not
+    // guaranteed to use the fewest pushes.
+    #[default]
+    Greedy,
+    // Minimum-cost perfect matching between boxes and goals. Never overestimates, which
+    // makes the solver return push-optimal solutions at the price of an O(n³) estimate.
+    Hungarian,
+}
+
+impl Heuristic {
+    // Returns `u16::MAX` when the boxes cannot all be matched to distinct goals.
+    pub(crate) fn estimate(self, boxes: &BoxOrGoal, distance_maps: &[DistanceMap]) -> u16 {
+        match self {
+            Heuristic::Greedy => heuristic_greedy_match(boxes, distance_maps),
+            Heuristic::Hungarian => heuristic_hungarian(boxes, distance_maps),
+        }
+    }
+
+    pub(crate) fn is_admissible(self) -> bool {
+        matches!(self, Heuristic::Hungarian)
+    }
+}
+
+// Instead of just summing distances, we ensure no two boxes count the same goal.
+fn heuristic_greedy_match(boxes: &BoxOrGoal, distance_maps: &[DistanceMap]) -> u16 {
+    // 1. Collect all possible pairings (distance, box_index, map_index)
+    // Up to 11 boxes fit on the stack (121 edges), bigger levels spill to the heap.
+    let mut edges: SmallVec<[(u16, u16, u16); 128]> = SmallVec::new();
+
+    for (b_idx, &box_pos) in boxes.iter().enumerate() {
+        for (m_idx, map) in distance_maps.iter().enumerate() {
+            let d = map[box_pos];
+            if d != u16::MAX {
+                edges.push((d, b_idx as u16, m_idx as u16));
+            }
+        }
+    }
+
+    // 2. Sort edges by distance (cheapest moves first)
+    edges.sort_unstable_by_key(|edge| edge.0);
+
+    let mut total_cost: u16 = 0;
+    let box_count = boxes.len();
+    let mut matched_count = 0;
+    let mut matched_boxes: SmallVec<[bool; 64]> = SmallVec::from_elem(false, box_count);
+    let mut taken_goals: SmallVec<[bool; 64]> = SmallVec::from_elem(false, distance_maps.len());
+
+    // 3. Greedily assign
+    for (dist, b_idx, m_idx) in edges {
+        let (b_idx, m_idx) = (b_idx as usize, m_idx as usize);
+
+        // If this box and this goal are both free, take them
+        if !matched_boxes[b_idx] && !taken_goals[m_idx] {
+            // `u16::MAX` is reserved for dead states, so a huge sum must stay below it.
+            total_cost = total_cost.saturating_add(dist).min(u16::MAX - 1);
+            matched_boxes[b_idx] = true;
+            taken_goals[m_idx] = true;
+            matched_count += 1;
+
+            // Optimization: Stop if all boxes matched
+            if matched_count == box_count {
+                return total_cost;
+            }
+        }
+    }
+
+    // Greedy picks can strand a box whose only reachable goals were taken by cheaper pairs,
+    // and push distances leave many pairs unreachable. Only a real matching can tell whether
+    // this is a DEAD STATE (e.g., 2 boxes trapped in a tunnel with only 1 goal accessible).
+    heuristic_hungarian(boxes, distance_maps)
+}
+
+// Kuhn-Munkres with row/column potentials over the `boxes × goals` distance matrix.
+fn heuristic_hungarian(boxes: &BoxOrGoal, distance_maps: &[DistanceMap]) -> u16 {
+    let n = boxes.len();
+    let m = distance_maps.len();
+    if n == 0 {
+        return 0;
+    }
+    if n > m {
+        return u16::MAX;
+    }
+
+    // An unreachable pair costs more than any matching made of reachable pairs only.
+    let unreachable = (n as i64 + 1) * u16::MAX as i64;
+    let cost = |b: usize, g: usize| match distance_maps[g][boxes[b]] {
+        u16::MAX => unreachable,
+        d => d as i64,
+    };
+
+    // 1-based indices, row 0 and column 0 are the virtual start of each augmenting path.
+    let mut u = vec![0i64; n + 1];
+    let mut v = vec![0i64; m + 1];
+    let mut matched_row = vec![0usize; m + 1]; // Box matched to each goal column
+    let mut way = vec![0usize; m + 1];
+    let mut min_slack = vec![0i64; m + 1];
+    let mut used = vec![false; m + 1];
+
+    for row in 1..=n {
+        matched_row[0] = row;
+        let mut col0 = 0;
+        min_slack.fill(i64::MAX);
+        used.fill(false);
+
+        loop {
+            used[col0] = true;
+            let row0 = matched_row[col0];
+            let mut delta = i64::MAX;
+            let mut col1 = 0;
+
+            for col in 1..=m {
+                if used[col] {
+                    continue;
+                }
+                let slack = cost(row0 - 1, col - 1) - u[row0] - v[col];
+                if slack < min_slack[col] {
+                    min_slack[col] = slack;
+                    way[col] = col0;
+                }
+                if min_slack[col] < delta {
+                    delta = min_slack[col];
+                    col1 = col;
+                }
+            }
+
+            for col in 0..=m {
+                if used[col] {
+                    u[matched_row[col]] += delta;
+                    v[col] -= delta;
+                } else {
+                    min_slack[col] -= delta;
+                }
+            }
+
+            col0 = col1;
+            if matched_row[col0] == 0 {
+                break;
+            }
+        }
+
+        // Flip the augmenting path back to the start.
+        loop {
+            let col1 = way[col0];
+            matched_row[col0] = matched_row[col1];
+            col0 = col1;
+            if col0 == 0 {
+                break;
+            }
+        }
+    }
+
+    let total: i64 = (1..=m)
+        .filter(|&col| matched_row[col] != 0)
+        .map(|col| cost(matched_row[col] - 1, col - 1))
+        .sum();
+
+    // Some box had to take an unreachable goal: a DEAD STATE, same as the greedy matcher.
+    if total >= unreachable {
+        return u16::MAX;
+    }
+    total.min(u16::MAX as i64 - 1) as u16
+}
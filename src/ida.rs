@@ -0,0 +1,301 @@
+use std::mem::{replace, size_of, take};
+use std::time::Instant;
+
+use crate::expand::{Expander, Moves};
+use crate::search::solution_from;
+use crate::visited::{Visit, Visited};
+use crate::zobrist::Zobrist;
+use crate::{
+    BoolGrid, BoxOrGoal, LIMIT_CHECK_INTERVAL, Level, Limit, Node, Pos, Progress, ROOT,
+    SearchObserver, SearchStats, SolveOutcome, SolverConfig, get_normalized_player,
+};
+
+// Without `SolverConfig::transposition_table_mb` IDA* still remembers this much, otherwise it
+// would walk the same box layouts over and over along different push orders.
+const DEFAULT_TABLE_BYTES: usize = 32 << 20;
+
+// A child of the state being expanded, kept until its siblings are known so the most
+// promising one is searched first.
+struct Child {
+    boxes: BoxOrGoal,
+    player: Pos,
+    hash: u64,
+    push: Node,
+    estimate: u16,
+}
+
+// A child left out of the pass for its priority. Only once the pass is over is it known
+// whether the state was searched anyway, through a cheaper path found later.
+struct Skipped {
+    hash: u64, // Of the state, with the normalized player
+    boxes: BoxOrGoal,
+    player: Pos, // Normalized
+    cost: u16,
+    priority: u16,
+}
+
+// How many skipped children with the lowest priorities a pass keeps for the next threshold.
+const MAX_SKIPPED: usize = 1024;
+
+// Why a depth-first pass returned.
+enum Step {
+    Found,
+    Exhausted, // Every state under the threshold was searched
+    Stopped,   // A limit or the cancel token fired, see `Ida::stopped`
+}
+
+enum Stop {
+    Limit(Limit),
+    Cancelled,
+}
+
+// Iterative-deepening A*: depth-first passes that skip every state whose priority is above a
+// threshold, raising it to the lowest skipped priority after each pass. Memory is the current
+// push sequence plus a fixed-size table that prunes states already reached more cheaply in
+// the same pass.
+struct Ida<'a, O: SearchObserver + ?Sized> {
+    level: &'a Level,
+    config: &'a SolverConfig,
+    observer: &'a mut O,
+    zobrist: Zobrist,
+    visited: Visited,
+    earlier_evictions: usize, // Evictions of the tables of finished passes
+    expander: Expander<'a>,
+    norm_buffer: BoolGrid,
+    stack: Vec<Pos>,
+    pushes: Vec<Node>, // The push sequence leading to the current state, the root first
+    threshold: u16,
+    skipped: Vec<Skipped>,
+    dropped: u16, // Lowest priority of the skipped children that did not fit in `skipped`
+    stats: SearchStats,
+    start_time: Instant,
+    stopped: Option<Stop>,
+}
+
+pub(crate) fn solve_ida<O: SearchObserver + ?Sized>(
+    level: &Level,
+    config: &SolverConfig,
+    observer: &mut O,
+) -> SolveOutcome {
+    let grid = &level.grid;
+    let boxes = level.boxes.clone();
+    let table_bytes = config.table_bytes.unwrap_or(DEFAULT_TABLE_BYTES);
+
+    let mut ida = Ida {
+        level,
+        config,
+        zobrist: Zobrist::new(grid),
+        visited: Visited::new(grid, boxes.len(), Some(table_bytes)),
+        earlier_evictions: 0,
+        expander: Expander::new(level, config, Moves::Push),
+        norm_buffer: grid.map(false),
+        stack: Vec::with_capacity(grid.len()),
+        pushes: vec![Node {
+            parent: ROOT,
+            box_from: 0,
+            dir: 0,
+        }],
+        threshold: config.heuristic.estimate(&boxes, &level.goal_maps),
+        skipped: Vec::new(),
+        dropped: u16::MAX,
+        stats: SearchStats::default(),
+        start_time: Instant::now(),
+        stopped: None,
+        observer,
+    };
+    let hash = ida.zobrist.boxes(&boxes);
+    let mut norm_stack = Vec::new();
+    let norm_player = get_normalized_player(
+        level.player,
+        &boxes,
+        grid,
+        &mut ida.norm_buffer,
+        &mut norm_stack,
+    );
+    let root_hash = ida.zobrist.state(hash, norm_player);
+
+    while ida.threshold != u16::MAX {
+        ida.observer.on_new_best_lower_bound(ida.threshold as usize);
+        ida.earlier_evictions += ida.visited.evictions();
+        ida.visited = Visited::new(grid, boxes.len(), Some(table_bytes));
+        ida.visited.visit(root_hash, &boxes, norm_player, 0, true);
+
+        match ida.search(&boxes, level.player, hash, 0) {
+            Step::Found => {
+                ida.update_stats();
+                let last = ida.pushes.len() as u32 - 1;
+                return SolveOutcome::Solved(solution_from(level, &ida.pushes, last, ida.stats));
+            }
+            Step::Exhausted => ida.threshold = ida.next_threshold(),
+            Step::Stopped => {
+                ida.update_stats();
+                return match ida.stopped {
+                    Some(Stop::Limit(limit)) => SolveOutcome::LimitReached {
+                        limit,
+                        stats: ida.stats,
+                    },
+                    _ => SolveOutcome::Cancelled { stats: ida.stats },
+                };
+            }
+        }
+    }
+
+    // A whole pass found nothing beyond the threshold: every reachable state was searched.
+    ida.update_stats();
+    SolveOutcome::Unsolvable { stats: ida.stats }
+}
+
+impl<O: SearchObserver + ?Sized> Ida<'_, O> {
+    fn search(&mut self, boxes: &BoxOrGoal, player: Pos, hash: u64, cost: u16) -> Step {
+        let level = self.level;
+        let grid = &level.grid;
+        let goals = &level.goals;
+
+        self.stats.nodes_expanded += 1;
+        self.observer
+            .on_expand(cost as usize, self.threshold as usize);
+        if boxes.iter().all(|b| goals.contains(b)) {
+            return Step::Found;
+        }
+        if self.should_stop() {
+            return Step::Stopped;
+        }
+
+        // On the heap: the recursion goes as deep as the solution is long.
+        let mut children: Vec<Child> = Vec::new();
+        let parent = self.pushes.len() as u32 - 1;
+        self.expander
+            .for_each_child(boxes, player, &mut self.stats, |boxes, step, stats| {
+                let estimate = self.config.heuristic.estimate(&boxes, &level.goal_maps);
+                if estimate == u16::MAX {
+                    stats.deadlocks.unmatched += 1;
+                    return;
+                }
+                children.push(Child {
+                    hash: self.zobrist.push(hash, step.from, step.to),
+                    boxes,
+                    player: step.player,
+                    push: Node {
+                        parent,
+                        box_from: step.from,
+                        dir: step.dir,
+                    },
+                    estimate,
+                });
+            });
+
+        // Closest to the goal first, the pass ends as soon as a solution shows up.
+        children.sort_by_key(|child| child.estimate);
+
+        let new_cost = cost + 1;
+        for child in children {
+            self.stack.clear();
+            let norm_player = get_normalized_player(
+                child.player,
+                &child.boxes,
+                grid,
+                &mut self.norm_buffer,
+                &mut self.stack,
+            );
+            let state_hash = self.zobrist.state(child.hash, norm_player);
+
+            let priority = new_cost.saturating_add(child.estimate);
+            if priority > self.threshold {
+                // Already searched more cheaply in this pass, a revisit along a cycle.
+                if self
+                    .visited
+                    .is_known(state_hash, &child.boxes, norm_player, new_cost)
+                {
+                    self.stats.duplicates_pruned += 1;
+                } else {
+                    self.skip(Skipped {
+                        hash: state_hash,
+                        boxes: child.boxes,
+                        player: norm_player,
+                        cost: new_cost,
+                        priority,
+                    });
+                }
+                continue;
+            }
+
+            // Reached more cheaply earlier in this pass, that visit already searched deeper.
+            if let Visit::Known =
+                self.visited
+                    .visit(state_hash, &child.boxes, norm_player, new_cost, true)
+            {
+                self.stats.duplicates_pruned += 1;
+                continue;
+            }
+
+            self.stats.nodes_generated += 1;
+            self.pushes.push(child.push);
+            self.stats.peak_open = self.stats.peak_open.max(self.pushes.len() - 1);
+
+            match self.search(&child.boxes, child.player, child.hash, new_cost) {
+                Step::Exhausted => {}
+                step => return step,
+            }
+            self.pushes.pop();
+        }
+
+        Step::Exhausted
+    }
+
+    fn skip(&mut self, skipped: Skipped) {
+        if self.skipped.len() == 2 * MAX_SKIPPED {
+            self.skipped.sort_unstable_by_key(|s| s.priority);
+            self.dropped = self.dropped.min(self.skipped[MAX_SKIPPED].priority);
+            self.skipped.truncate(MAX_SKIPPED);
+        }
+        self.skipped.push(skipped);
+    }
+
+    // The lowest priority of a skipped child whose state the finished pass never searched at
+    // the same cost or less, `u16::MAX` if there is none. Children reached through an expensive
+    // path before the cheap one do not count, or reversible pushes would raise the threshold
+    // one pass at a time forever instead of proving the level unsolvable.
+    fn next_threshold(&mut self) -> u16 {
+        let mut skipped = take(&mut self.skipped);
+        skipped.sort_unstable_by_key(|s| s.priority);
+        let next = skipped
+            .iter()
+            .find(|s| !self.visited.is_known(s.hash, &s.boxes, s.player, s.cost))
+            .map_or(u16::MAX, |s| s.priority);
+        skipped.clear();
+        self.skipped = skipped;
+        next.min(replace(&mut self.dropped, u16::MAX))
+    }
+
+    fn should_stop(&mut self) -> bool {
+        let expanded = self.stats.nodes_expanded;
+        if expanded.is_multiple_of(LIMIT_CHECK_INTERVAL) {
+            self.update_stats();
+        }
+        if expanded.is_multiple_of(self.config.progress_interval) {
+            self.update_stats();
+            self.observer.on_progress(&Progress {
+                stats: &self.stats,
+                open_len: self.pushes.len() - 1,
+                visited_len: self.visited.len(),
+                f_bound: self.threshold as usize,
+            });
+        }
+
+        if self.config.is_cancelled() {
+            self.stopped = Some(Stop::Cancelled);
+        } else if let Some(limit) = self.config.exceeded(&self.stats) {
+            self.stopped = Some(Stop::Limit(limit));
+        }
+        self.stopped.is_some()
+    }
+
+    fn update_stats(&mut self) {
+        self.stats.elapsed = self.start_time.elapsed();
+        self.stats.memory_bytes = self.visited.memory_bytes()
+            + self.pushes.capacity() * size_of::<Node>()
+            + self.skipped.capacity() * size_of::<Skipped>();
+        self.stats.peak_visited = self.stats.peak_visited.max(self.visited.len());
+        self.stats.evictions = self.earlier_evictions + self.visited.evictions();
+    }
+}
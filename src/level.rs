@@ -0,0 +1,318 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::distance::{compute_push_distance_map, side_blocks};
+use crate::formations::Formations;
+use crate::grid::Grid;
+use crate::{BoolGrid, BoxOrGoal, DIRECTIONS, DistanceMap, Pos, dead_squares};
+
+// Everything that can be wrong with a level before the search even starts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LevelError {
+    Empty,
+    TooLarge { width: usize, height: usize },
+    UnknownChar { row: usize, col: usize, ch: char },
+    NoPlayer,
+    MultiplePlayers { count: usize },
+    BoxGoalMismatch { boxes: usize, goals: usize },
+    NotEnclosed { row: usize, col: usize },
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelError::Empty => write!(f, "level has no rows or all rows are empty"),
+            LevelError::TooLarge { width, height } => write!(
+                f,
+                "level too big: {}x{} cells cannot be addressed by a position",
+                width, height
+            ),
+            LevelError::UnknownChar { row, col, ch } => {
+                write!(
+                    f,
+                    "unknown character {:?} at row {}, column {}",
+                    ch, row, col
+                )
+            }
+            LevelError::NoPlayer => write!(f, "level has no player"),
+            LevelError::MultiplePlayers { count } => {
+                write!(f, "level has {} players, expected exactly one", count)
+            }
+            LevelError::BoxGoalMismatch { boxes, goals } => {
+                write!(f, "level has {} boxes but {} goals", boxes, goals)
+            }
+            LevelError::NotEnclosed { row, col } => write!(
+                f,
+                "level is not enclosed by walls, row {}, column {} can be reached from outside",
+                row, col
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+// A validated level, parsed once and shared by every solve and analysis on it.
+// The static analysis (dead squares, goal distances) only depends on walls and goals,
+// so it is computed here instead of on every call to the solver.
+#[derive(Clone)]
+pub struct Level {
+    pub(crate) grid: Grid, // Exterior cells are stored as walls
+    pub(crate) exterior: BoolGrid,
+    pub(crate) player: Pos,
+    pub(crate) boxes: BoxOrGoal, // Sorted, see `Level::parse`
+    pub(crate) goals: BoxOrGoal,
+    pub(crate) dead: BoolGrid, // Squares a box can never leave towards a goal
+    pub(crate) goal_maps: Vec<DistanceMap>, // Push distances per goal, same order as `goals`
+    pub(crate) formations: Formations,
+}
+
+impl Level {
+    pub fn parse(level: &[&str]) -> Result<Level, LevelError> {
+        let height = level.len();
+        let width = level.iter().map(|row| row.len()).max().unwrap_or(0);
+
+        if width == 0 {
+            return Err(LevelError::Empty);
+        }
+        // Tables are sized to the level, the only limit is that every cell needs a `Pos`.
+        let fits = width
+            .checked_mul(height)
+            .is_some_and(|cells| cells <= Pos::MAX as usize);
+        if !fits {
+            return Err(LevelError::TooLarge { width, height });
+        }
+
+        let mut grid = Grid::new(width, height);
+        let mut players = Vec::new();
+        let mut boxes = BoxOrGoal::new();
+        let mut goals = BoxOrGoal::new();
+
+        for (r, row) in level.iter().enumerate() {
+            for (c, ch) in row.chars().enumerate() {
+                // `-` and `_` are the common floor aliases used in XSB files.
+                let ch = match ch {
+                    '-' | '_' => ' ',
+                    '#' | ' ' | '@' | '+' | '$' | '*' | '.' => ch,
+                    _ => return Err(LevelError::UnknownChar { row: r, col: c, ch }),
+                };
+                let pos = grid.pos(r, c);
+                grid[pos] = ch as u8;
+
+                match ch {
+                    '@' | '+' => players.push(pos),
+                    '$' | '*' => boxes.push(pos),
+                    _ => {}
+                }
+                if matches!(ch, '.' | '*' | '+') {
+                    goals.push(pos);
+                }
+            }
+        }
+
+        let player = match players.as_slice() {
+            [] => return Err(LevelError::NoPlayer),
+            [player] => *player,
+            _ => {
+                return Err(LevelError::MultiplePlayers {
+                    count: players.len(),
+                });
+            }
+        };
+        if boxes.len() != goals.len() {
+            return Err(LevelError::BoxGoalMismatch {
+                boxes: boxes.len(),
+                goals: goals.len(),
+            });
+        }
+
+        // Anything outside the outer wall becomes a wall itself, so every cell the search can
+        // visit has all four neighbours inside the grid and `Grid::step` never leaves it.
+        let exterior = mark_exterior(&grid);
+        let mut outside = (0..grid.len() as Pos).filter(|&pos| exterior[pos]);
+        if let Some(pos) =
+            outside.find(|pos| *pos == player || boxes.contains(pos) || goals.contains(pos))
+        {
+            let (row, col) = grid.coords(pos);
+            return Err(LevelError::NotEnclosed { row, col });
+        }
+        for pos in 0..grid.len() as Pos {
+            if exterior[pos] {
+                grid[pos] = b'#';
+            }
+        }
+
+        // Keep boxes in a fixed order so the same setup isn't counted twice
+        // e.g. [(2,3),(4,5)] and [(4,5),(2,3)] are treated the same.
+        boxes.sort_unstable();
+
+        let mut dead = grid.map(true);
+        dead_squares(&grid, &goals, &mut dead);
+
+        let blocks = side_blocks(&grid);
+        let goal_maps: Vec<DistanceMap> = goals
+            .iter()
+            .map(|&goal| compute_push_distance_map(goal, &grid, &blocks))
+            .collect();
+
+        // A box that cannot be pushed to any goal is dead too, even when the plain
+        // reverse flood in `dead_squares` reached its square.
+        for pos in 0..grid.len() as Pos {
+            if goal_maps.iter().all(|map| map[pos] == u16::MAX) {
+                dead[pos] = true;
+            }
+        }
+
+        let formations = Formations::new(&grid, &goals, &mut dead);
+
+        Ok(Level {
+            grid,
+            exterior,
+            player,
+            boxes,
+            goals,
+            dead,
+            goal_maps,
+            formations,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.grid.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.grid.height()
+    }
+
+    // Positions are (row, col) pairs, counted from the top-left corner.
+    pub fn player(&self) -> (usize, usize) {
+        self.grid.coords(self.player)
+    }
+
+    pub fn boxes(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.boxes.iter().map(|&pos| self.grid.coords(pos))
+    }
+
+    pub fn goals(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.goals.iter().map(|&pos| self.grid.coords(pos))
+    }
+
+    pub fn walls(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.cells().filter(|&(r, c)| self.is_wall(r, c))
+    }
+
+    pub fn floor(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.cells().filter(|&(r, c)| self.is_floor(r, c))
+    }
+
+    pub fn is_wall(&self, row: usize, col: usize) -> bool {
+        self.in_bounds(row, col) && self.grid.is_wall(self.grid.pos(row, col))
+    }
+
+    // Cells outside the outer wall, including the padding of short rows, count as walls.
+    pub fn is_exterior(&self, row: usize, col: usize) -> bool {
+        self.in_bounds(row, col) && self.exterior[self.grid.pos(row, col)]
+    }
+
+    // Anything inside the outer wall that is not a wall itself.
+    pub fn is_floor(&self, row: usize, col: usize) -> bool {
+        self.in_bounds(row, col) && !self.grid.is_wall(self.grid.pos(row, col))
+    }
+
+    pub fn is_goal(&self, row: usize, col: usize) -> bool {
+        self.goals().any(|goal| goal == (row, col))
+    }
+
+    pub fn has_box(&self, row: usize, col: usize) -> bool {
+        self.boxes().any(|b| b == (row, col))
+    }
+
+    // Fewest pushes that bring a lone box on (row, col) to its nearest goal, `None` if no goal
+    // can be reached because the player can never get behind the box.
+    pub fn push_distance(&self, row: usize, col: usize) -> Option<usize> {
+        if !self.is_floor(row, col) {
+            return None;
+        }
+        let pos = self.grid.pos(row, col);
+        let nearest = self.goal_maps.iter().map(|map| map[pos]).min()?;
+        (nearest != u16::MAX).then_some(nearest as usize)
+    }
+
+    fn in_bounds(&self, row: usize, col: usize) -> bool {
+        row < self.height() && col < self.width()
+    }
+
+    fn cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.height()).flat_map(move |r| (0..self.width()).map(move |c| (r, c)))
+    }
+}
+
+// Accepts the whole level as one string, one row per line.
+// Blank lines around the level are ignored, so raw string literals can be used as-is.
+impl FromStr for Level {
+    type Err = LevelError;
+
+    fn from_str(level: &str) -> Result<Level, LevelError> {
+        let rows: Vec<&str> = level.lines().collect();
+        let first = rows.iter().position(|row| !row.trim().is_empty());
+        let last = rows.iter().rposition(|row| !row.trim().is_empty());
+
+        match (first, last) {
+            (Some(first), Some(last)) => Level::parse(&rows[first..=last]),
+            _ => Err(LevelError::Empty),
+        }
+    }
+}
+
+// Renders the level back in the standard XSB notation.
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (r, row) in self.grid.rows().enumerate() {
+            let row: String = row
+                .iter()
+                .enumerate()
+                .map(|(c, &ch)| {
+                    if self.is_exterior(r, c) {
+                        ' '
+                    } else {
+                        ch as char
+                    }
+                })
+                .collect();
+            writeln!(f, "{}", row.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+// Flood fill from every open cell on the border, whatever it reaches is outside the level.
+fn mark_exterior(grid: &Grid) -> BoolGrid {
+    let mut exterior = grid.map(false);
+    let (width, height) = (grid.width(), grid.height());
+
+    let mut stack: Vec<Pos> = (0..grid.len() as Pos)
+        .filter(|&pos| {
+            let (row, col) = grid.coords(pos);
+            row == 0 || col == 0 || row == height - 1 || col == width - 1
+        })
+        .filter(|&pos| !grid.is_wall(pos))
+        .collect();
+    for &pos in &stack {
+        exterior[pos] = true;
+    }
+
+    while let Some(pos) = stack.pop() {
+        for (dr, dc, _) in DIRECTIONS {
+            let Some(next) = grid.checked_step(pos, dr, dc) else {
+                continue;
+            };
+
+            if !grid.is_wall(next) && !exterior[next] {
+                exterior[next] = true;
+                stack.push(next);
+            }
+        }
+    }
+    exterior
+}
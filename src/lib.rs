@@ -2,29 +2,73 @@ use ahash::AHashSet;
 use smallvec::SmallVec;
 
 use std::cmp::Ordering;
-use std::collections::BinaryHeap;
 use std::collections::VecDeque;
-
-// Each position uses i8 (avoiding casting hell), hence the map cannot exceed 127×127 size.
-type Pos = (i8, i8);
-type BoxOrGoal = SmallVec<[Pos; 15]>;
-type Grid = [[char; MAX_SIZE]; MAX_SIZE];
-type BoolGrid = [[bool; MAX_SIZE]; MAX_SIZE];
-type PathGrid = [[u8; MAX_SIZE]; MAX_SIZE];
-type DistanceMap = [[u16; MAX_SIZE]; MAX_SIZE];
-
-const MAX_SIZE: usize = 127;
-const DIRECTIONS: [(i8, i8, u8); 4] = [(1, 0, b'D'), (-1, 0, b'U'), (0, 1, b'R'), (0, -1, b'L')];
+use std::time::Instant;
+
+mod cancel;
+mod config;
+mod corral;
+mod distance;
+mod expand;
+mod formations;
+mod grid;
+mod heuristic;
+mod ida;
+mod level;
+mod matching;
+mod observer;
+mod outcome;
+mod parallel;
+mod patterns;
+mod portfolio;
+mod reverse;
+mod search;
+mod visited;
+mod zobrist;
+
+pub use cancel::CancelToken;
+pub use config::{Algorithm, SolverConfig};
+pub use heuristic::Heuristic;
+pub use level::{Level, LevelError};
+pub use observer::{Progress, SearchObserver};
+pub use outcome::{DeadlockStats, Limit, SearchStats, SolveOutcome, ThreadStats};
+pub use patterns::PatternDatabase;
+pub use portfolio::{Portfolio, PortfolioOutcome, solve_portfolio};
+
+use grid::{CellMap, Grid};
+use search::Frontier;
+
+// A position is the index of a cell in the `Grid`, see `Grid::pos`.
+pub(crate) type Pos = u32;
+pub(crate) type BoxOrGoal = SmallVec<[Pos; 15]>;
+pub(crate) type BoolGrid = CellMap<bool>;
+type PathGrid = CellMap<u8>;
+pub(crate) type DistanceMap = CellMap<u16>;
+
+pub(crate) const DIRECTIONS: [(i8, i8, u8); 4] =
+    [(1, 0, b'D'), (-1, 0, b'U'), (0, 1, b'R'), (0, -1, b'L')];
 
 #[derive(Clone, Eq, PartialEq)]
 struct State {
     boxes: BoxOrGoal,
     player: Pos,
-    pushes: SmallVec<[u8; 128]>, // Now stores: PlayerPath + PushDir
-    cost: u16,                   // Number of pushes made so far
-    priority: u16,               // cost + heuristic
+    hash: u64,     // Zobrist hash of `boxes`
+    node: u32,     // Index of the push that led here in the node arena
+    cost: u16,     // Number of pushes made so far
+    priority: u16, // cost + heuristic
+}
+
+// One entry of the search tree. States only keep an index into the arena, the LURD path is
+// rebuilt from the chain of pushes once a goal is found.
+#[derive(Clone, Copy)]
+struct Node {
+    parent: u32,   // The root is its own parent
+    box_from: Pos, // Box position before the push
+    dir: u8,       // Index into `DIRECTIONS`
 }
 
+const ROOT: u32 = 0;
+
 // Tie-Breaking optimization If Priority (F) is equal, prefer higher Cost (G).
 // This makes A* behave like Depth-First Search on ties, finding solutions faster.
 impl Ord for State {
@@ -46,257 +90,134 @@ impl Default for State {
     fn default() -> Self {
         Self {
             boxes: SmallVec::new(),
-            player: (0, 0),
-            pushes: SmallVec::new(),
+            player: 0,
+            hash: 0,
+            node: ROOT,
             cost: 0,
             priority: 0,
         }
     }
 }
 
+// A solved level: the LURD path plus what it took to find it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Solution {
+    pub path: String,               // LURD notation, uppercase letters are pushes
+    pub pushes: usize,              // Number of box pushes in `path`
+    pub moves: usize,               // Number of player steps in `path`, pushes included
+    pub boxes: Vec<(usize, usize)>, // Final box layout as sorted (row, col) pairs
+    pub stats: SearchStats,
+}
+
 pub fn solve(level: &[&str]) -> Option<String> {
-    let height = level.len();
-    let width = level.iter().map(|row| row.len()).max();
+    solve_detailed(level).map(|solution| solution.path)
+}
 
-    let width = width.expect("Level has no rows or all rows are empty");
-    if height > MAX_SIZE || width > MAX_SIZE {
-        panic!("Level too big: max size is {}x{}", MAX_SIZE, MAX_SIZE);
-    }
+// Panics if the level is malformed, use `try_solve` to get a `LevelError` instead.
+pub fn solve_detailed(level: &[&str]) -> Option<Solution> {
+    try_solve(level).unwrap_or_else(|err| panic!("Invalid level: {}", err))
+}
+
+pub fn try_solve(level: &[&str]) -> Result<Option<Solution>, LevelError> {
+    let level = Level::parse(level)?;
+    Ok(solve_level(&level))
+}
 
-    let mut grid: Grid = [[' '; MAX_SIZE]; MAX_SIZE];
-    let mut initial_player = (0, 0);
-    let mut initial_boxes = BoxOrGoal::new();
+// Solves an already parsed level, reusing its precomputed dead squares and distance maps.
+pub fn solve_level(level: &Level) -> Option<Solution> {
+    solve_with(level, &SolverConfig::default()).into_solution()
+}
 
-    let mut goals = BoxOrGoal::new();
+// How often the clock and the memory estimate are checked, both are too slow for every node.
+const LIMIT_CHECK_INTERVAL: usize = 1024;
 
-    for (r, row) in level.iter().enumerate().take(height) {
-        for (c, &byte) in row.as_bytes().iter().enumerate() {
-            let char = byte as char;
-            grid[r][c] = char;
+pub fn solve_with(level: &Level, config: &SolverConfig) -> SolveOutcome {
+    solve_observed(level, config, &mut ())
+}
 
-            let pos = (r as i8, c as i8);
-            match char {
-                '@' | '+' => initial_player = pos,
-                '$' | '*' => initial_boxes.push(pos),
-                _ => {}
-            }
-            if matches!(char, '.' | '*' | '+') {
-                goals.push(pos);
-            }
+pub fn solve_observed<O: SearchObserver + ?Sized>(
+    level: &Level,
+    config: &SolverConfig,
+    observer: &mut O,
+) -> SolveOutcome {
+    match config.algorithm {
+        Algorithm::AStar | Algorithm::GreedyBestFirst => {
+            solve_frontiers(config, observer, || vec![Frontier::forward(level, config)])
+        }
+        Algorithm::IdaStar => ida::solve_ida(level, config, observer),
+        Algorithm::Reverse => {
+            solve_frontiers(config, observer, || vec![Frontier::backward(level, config)])
         }
+        Algorithm::ParallelAStar => parallel::solve_parallel(level, config, observer),
+        Algorithm::Bidirectional => solve_frontiers(config, observer, || {
+            vec![
+                Frontier::forward(level, config),
+                Frontier::backward(level, config),
+            ]
+        }),
     }
+}
 
-    // Keep boxes in a fixed order so the same setup isn't counted twice
-    // e.g. [(2,3),(4,5)] and [(4,5),(2,3)] are treated the same.
-    initial_boxes.sort_unstable();
-
-    let mut dead = [[true; MAX_SIZE]; MAX_SIZE];
-    dead_squares(&grid, &goals, &mut dead);
-
-    // Using in-place mutation avoids cloning and heap allocation, making the flood fill faster.
-    let mut reachable = [[false; MAX_SIZE]; MAX_SIZE];
-    let mut came_from: PathGrid = [[0; MAX_SIZE]; MAX_SIZE];
-    let mut queue_buf: VecDeque<Pos> = VecDeque::new();
+// Best-first search over the frontiers `new_frontiers` builds. The clock starts before they
+// are built, their setup counts towards the time limit.
+fn solve_frontiers<'a, O: SearchObserver + ?Sized>(
+    config: &SolverConfig,
+    observer: &mut O,
+    new_frontiers: impl FnOnce() -> Vec<Frontier<'a>>,
+) -> SolveOutcome {
+    let start_time = Instant::now();
+    search::best_first(new_frontiers(), config, observer, start_time)
+}
 
-    let mut goal_maps: Vec<DistanceMap> = Vec::with_capacity(goals.len());
-    for &goal_pos in &goals {
-        goal_maps.push(compute_distance_map(goal_pos, &grid));
+// Replays the pushes from the root to `last` on the initial layout, walking the player to
+// each push along the same shortest path the search saw when it generated that push.
+fn rebuild_path(
+    nodes: &[Node],
+    last: u32,
+    initial_player: Pos,
+    initial_boxes: &BoxOrGoal,
+    grid: &Grid,
+) -> String {
+    let mut chain = Vec::new();
+    let mut current = last;
+    while current != ROOT {
+        chain.push(nodes[current as usize]);
+        current = nodes[current as usize].parent;
     }
 
-    // SETUP A* SEARCH
-    // Buffer 2: Temporary buffer for calculating normalized player in FUTURE states
-    let mut visited: AHashSet<(BoxOrGoal, Pos)> = AHashSet::with_capacity(65536);
-    let mut queue: BinaryHeap<State> = BinaryHeap::new();
-    let mut norm_buffer = [[false; MAX_SIZE]; MAX_SIZE];
-    let mut norm_stack = Vec::with_capacity(MAX_SIZE * MAX_SIZE);
-
-    // Normalize initial state
-    let norm_player = get_normalized_player(
-        initial_player,
-        &initial_boxes,
-        &grid,
-        &mut norm_buffer,
-        &mut norm_stack,
-    );
-    visited.insert((initial_boxes.clone(), norm_player));
-
-    let initial_h = heuristic_greedy_match(&initial_boxes, &goal_maps);
-    // let initial_h = heuristic(&initial_boxes, &goal_maps);
-
-    queue.push(State {
-        boxes: initial_boxes,
-        player: initial_player,
-        pushes: SmallVec::new(),
-        cost: 0,
-        priority: initial_h,
-    });
-
-    let mut num_node = 0;
-    while let Some(state) = queue.pop() {
-        num_node += 1;
-        if state.boxes.iter().all(|b| goals.contains(b)) {
-            println!("num_branch: {}", num_node);
-            return Some(state.pushes.iter().map(|i| *i as char).collect::<String>());
-        }
-
+    let mut boxes = initial_boxes.clone();
+    let mut player = initial_player;
+    let mut reachable = grid.map(false);
+    let mut came_from: PathGrid = grid.map(0);
+    let mut queue_buf = VecDeque::new();
+    let mut path = String::new();
+
+    for node in chain.iter().rev() {
+        let (dr, dc, push_ch) = DIRECTIONS[node.dir as usize];
+        reachable.fill(false);
+        came_from.fill(0);
         queue_buf.clear();
-        for row in &mut reachable {
-            row.fill(false);
-        }
-        for row in &mut came_from {
-            row.fill(0);
-        }
-
-        // BFS to find all reachable squares and the shortest path (for moves) to them.
         mark_reachable_with_path(
-            state.player,
-            &state.boxes,
-            &grid,
+            player,
+            &boxes,
+            grid,
             &mut reachable,
             &mut came_from,
             &mut queue_buf,
         );
 
-        for (i, &box_position) in state.boxes.iter().enumerate() {
-            let (box_row, box_col) = box_position;
-            for &(dr, dc, push_ch) in &DIRECTIONS {
-                let (new_box_row, new_box_col) = (box_row + dr, box_col + dc);
-                let new_player_pos = (box_row - dr, box_col - dc);
-
-                if !reachable[new_player_pos.0 as usize][new_player_pos.1 as usize]
-                    || dead[new_box_row as usize][new_box_col as usize]
-                    || !is_free(new_box_row, new_box_col, &state.boxes, &grid)
-                {
-                    continue;
-                }
-
-                // Cloning `SmallVec` is very cheap.
-                let mut new_boxes = state.boxes.clone();
-                new_boxes[i] = (new_box_row, new_box_col);
-                new_boxes.sort_unstable();
-
-                if is_locked(&new_boxes, &goals, &grid)
-                    || is_square_deadlock(new_box_row, new_box_col, &new_boxes, &goals, &grid)
-                {
-                    continue;
-                }
-
-                norm_stack.clear();
-                let norm_player = get_normalized_player(
-                    box_position,
-                    &new_boxes,
-                    &grid,
-                    &mut norm_buffer,
-                    &mut norm_stack,
-                );
-
-                if !visited.insert((new_boxes.clone(), norm_player)) {
-                    continue;
-                }
-
-                let player_path = get_path(new_player_pos, state.player, &came_from, &grid);
-                let mut new_pushes = state.pushes.clone();
-                new_pushes.extend(player_path); // Append player movement
-                new_pushes.push(push_ch); // Append the actual box push
-
-                let new_cost = state.cost + 1;
-
-                let h = heuristic_greedy_match(&new_boxes, &goal_maps);
-                if h == u16::MAX {
-                    continue;
-                }
-
-                queue.push(State {
-                    boxes: new_boxes,
-                    player: box_position,
-                    pushes: new_pushes,
-                    cost: new_cost,
-                    priority: new_cost + h,
-                });
-            }
-        }
-    }
-
-    None
-}
+        let behind = grid.step(node.box_from, -dr, -dc);
+        let walk = get_path(behind, player, &came_from, grid);
+        path.extend(walk.iter().map(|&ch| ch as char));
+        path.push(push_ch as char);
 
-// Instead of just summing distances, we ensure no two boxes count the same goal.
-fn heuristic_greedy_match(boxes: &BoxOrGoal, distance_maps: &[DistanceMap]) -> u16 {
-    // 1. Collect all possible pairings (distance, box_index, map_index)
-    // Capacity: 15 boxes * 15 goals = 225 max edges. Stack allocation is fine.
-    let mut edges: SmallVec<[(u16, u8, u8); 128]> = SmallVec::new();
-
-    for (b_idx, &(br, bc)) in boxes.iter().enumerate() {
-        for (m_idx, map) in distance_maps.iter().enumerate() {
-            let d = map[br as usize][bc as usize];
-            if d != u16::MAX {
-                edges.push((d, b_idx as u8, m_idx as u8));
-            }
+        for pos in boxes.iter_mut().filter(|pos| **pos == node.box_from) {
+            *pos = grid.step(node.box_from, dr, dc);
         }
+        player = node.box_from;
     }
 
-    // 2. Sort edges by distance (cheapest moves first)
-    edges.sort_unstable_by(|a, b| a.0.cmp(&b.0));
-
-    let mut total_cost: u16 = 0;
-    let mut matched_boxes = 0u16; // Bitmask for boxes (max 15)
-    let mut taken_goals = 0u16; // Bitmask for goals (max 15)
-    let box_count = boxes.len();
-
-    // 3. Greedily assign
-    for (dist, b_idx, m_idx) in edges {
-        let b_mask = 1 << b_idx;
-        let g_mask = 1 << m_idx;
-
-        // If this box and this goal are both free, take them
-        if (matched_boxes & b_mask) == 0 && (taken_goals & g_mask) == 0 {
-            total_cost += dist;
-            matched_boxes |= b_mask;
-            taken_goals |= g_mask;
-
-            // Optimization: Stop if all boxes matched
-            if matched_boxes.count_ones() as usize == box_count {
-                return total_cost;
-            }
-        }
-    }
-
-    // If we couldn't match every box to a unique goal, this is a DEAD STATE.
-    // (e.g., 2 boxes trapped in a tunnel with only 1 goal accessible)
-    u16::MAX
-}
-
-// BFS to fill DistanceMap
-fn compute_distance_map(goal: Pos, grid: &Grid) -> DistanceMap {
-    let mut dist_map = [[u16::MAX; MAX_SIZE]; MAX_SIZE];
-    let mut queue = VecDeque::new();
-
-    let (gr, gc) = goal;
-    dist_map[gr as usize][gc as usize] = 0;
-    queue.push_back(goal);
-
-    while let Some((r, c)) = queue.pop_front() {
-        let current_dist = dist_map[r as usize][c as usize];
-        if current_dist == u16::MAX - 1 {
-            continue;
-        }
-
-        for (dr, dc, _) in DIRECTIONS {
-            let nr = r + dr;
-            let nc = c + dc;
-            let (unr, unc) = (nr as usize, nc as usize);
-
-            if unr < MAX_SIZE
-                && unc < MAX_SIZE
-                && grid[unr][unc] != '#'
-                && dist_map[unr][unc] == u16::MAX
-            {
-                dist_map[unr][unc] = current_dist + 1;
-                queue.push_back((nr, nc));
-            }
-        }
-    }
-    dist_map
+    path
 }
 
 // 2. Player Normalization
@@ -308,93 +229,111 @@ fn get_normalized_player(
     stack: &mut Vec<Pos>,
 ) -> Pos {
     // Reset buffers
-    for row in normalization_buffer.iter_mut() {
-        row.fill(false);
-    }
+    normalization_buffer.fill(false);
     stack.clear();
 
     // Use mark_reachable (the simpler version) for normalization
     mark_reachable(pos, boxes, grid, normalization_buffer, stack);
 
-    // Find top-left-most reachable square
-    for r in 0..MAX_SIZE {
-        for c in 0..MAX_SIZE {
-            if normalization_buffer[r][c] {
-                return (r as i8, c as i8);
-            }
-        }
+    // Find top-left-most reachable square, cells are numbered row by row.
+    match normalization_buffer.iter().position(|&reached| reached) {
+        Some(top_left) => top_left as Pos,
+        None => pos, // Should not happen if p is valid
     }
-    pos // Should not happen if p is valid
-}
-
-fn is_free(row: i8, col: i8, boxes: &BoxOrGoal, grid: &Grid) -> bool {
-    grid[row as usize][col as usize] != '#' && !boxes.contains(&(row, col))
 }
 
-fn is_wall(row: i8, col: i8, grid: &Grid) -> bool {
-    grid[row as usize][col as usize] == '#'
+fn is_free(pos: Pos, boxes: &BoxOrGoal, grid: &Grid) -> bool {
+    !grid.is_wall(pos) && !boxes.contains(&pos)
 }
 
-fn dead_squares(grid: &Grid, goals: &BoxOrGoal, dead: &mut BoolGrid) {
-    let height = grid.len();
+pub(crate) fn dead_squares(grid: &Grid, goals: &BoxOrGoal, dead: &mut BoolGrid) {
     let mut alive = AHashSet::new();
     let mut queue = Vec::new();
 
-    for (x, y) in goals {
-        alive.insert((*x, *y));
-        queue.push((*x, *y));
+    for &goal in goals {
+        alive.insert(goal);
+        queue.push(goal);
     }
 
-    while let Some((row, col)) = queue.pop() {
+    while let Some(pos) = queue.pop() {
         for (dr, dc, _) in DIRECTIONS {
-            let (prev_row, prev_col) = (row - dr, col - dc);
-            let (player_row, player_col) = (row - 2 * dr, col - 2 * dc);
-
-            let is_out_of_bounds = |r: i8, c: i8, h: usize, g: &Grid| {
-                r < 0 || r as usize >= h || c < 0 || c as usize >= g[r as usize].len()
+            let Some(prev) = grid.checked_step(pos, -dr, -dc) else {
+                continue;
             };
-
-            if is_out_of_bounds(prev_row, prev_col, height, grid)
-                || is_out_of_bounds(player_row, player_col, height, grid)
-            {
+            let Some(player) = grid.checked_step(prev, -dr, -dc) else {
                 continue;
-            }
+            };
 
-            if !is_wall(prev_row, prev_col, grid)
-                && !is_wall(player_row, player_col, grid)
-                && alive.insert((prev_row, prev_col))
-            {
-                queue.push((prev_row, prev_col));
+            if !grid.is_wall(prev) && !grid.is_wall(player) && alive.insert(prev) {
+                queue.push(prev);
             }
         }
     }
 
-    for (y, x) in alive.iter() {
-        dead[*y as usize][*x as usize] = false;
+    for &pos in alive.iter() {
+        dead[pos] = false;
     }
 }
 
-fn is_locked(boxes: &BoxOrGoal, goals: &BoxOrGoal, grid: &Grid) -> bool {
-    let is_blocked = |r, c| !is_free(r, c, boxes, grid);
-    boxes.iter().filter(|&b| !goals.contains(b)).any(|&(r, c)| {
-        let up = is_wall(r - 1, c, grid);
-        let down = is_wall(r + 1, c, grid);
-        let left = is_wall(r, c - 1, grid);
-        let right = is_wall(r, c + 1, grid);
-        let h_block = is_blocked(r, c - 1) || is_blocked(r, c + 1);
-        let v_block = is_blocked(r - 1, c) || is_blocked(r + 1, c);
-
-        (up && down && h_block) || (left && right && v_block)
-    })
+// Whether the push onto `box_pos` froze a box away from its goal. Only the pushed box can have
+// changed, any other box it freezes is frozen through it.
+fn is_freeze_deadlock(box_pos: Pos, boxes: &BoxOrGoal, level: &Level) -> bool {
+    let mut walls = BoxOrGoal::new();
+    let mut frozen = BoxOrGoal::new();
+    is_frozen(box_pos, boxes, level, &mut walls, &mut frozen)
+        && frozen.iter().any(|pos| !level.goals.contains(pos))
 }
 
-fn is_square_deadlock(
-    box_row: i8,
-    box_col: i8,
+// A box is frozen when it can move along neither axis. An axis is blocked by a wall on either
+// side, dead squares on both sides or a neighbouring box that is frozen itself. While checking
+// a neighbour, the boxes further up the chain in `walls` count as walls, which also ends the
+// recursion. The frozen boxes end up in `frozen`, goals or not.
+fn is_frozen(
+    pos: Pos,
     boxes: &BoxOrGoal,
-    goals: &BoxOrGoal,
-    grid: &Grid,
+    level: &Level,
+    walls: &mut BoxOrGoal,
+    frozen: &mut BoxOrGoal,
 ) -> bool {
+    let grid = &level.grid;
+    let mark = frozen.len();
+    walls.push(pos);
+
+    let axes = [(1, 0), (0, 1)];
+    let frozen_here = axes.iter().all(|&(dr, dc)| {
+        let sides = [grid.step(pos, -dr, -dc), grid.step(pos, dr, dc)];
+        sides
+            .iter()
+            .any(|&side| grid.is_wall(side) || walls.contains(&side))
+            || sides.iter().all(|&side| level.dead[side])
+            || sides
+                .iter()
+                .any(|&side| boxes.contains(&side) && is_frozen(side, boxes, level, walls, frozen))
+    });
+
+    if frozen_here {
+        // A wall can block an axis before the boxes along it are looked at. Those may be
+        // frozen by this box too, and `frozen` has to list them.
+        for (dr, dc) in axes {
+            for side in [grid.step(pos, -dr, -dc), grid.step(pos, dr, dc)] {
+                if boxes.contains(&side) && !walls.contains(&side) && !frozen.contains(&side) {
+                    is_frozen(side, boxes, level, walls, frozen);
+                }
+            }
+        }
+    }
+
+    walls.pop();
+    if frozen_here {
+        frozen.push(pos);
+    } else {
+        // Whatever froze below relied on this box staying put.
+        frozen.truncate(mark);
+    }
+    frozen_here
+}
+
+fn is_square_deadlock(box_pos: Pos, boxes: &BoxOrGoal, goals: &BoxOrGoal, grid: &Grid) -> bool {
     let quadrants = [
         (-1, -1), // Checks top-left quadrant
         (-1, 1),  // Checks top-right quadrant
@@ -403,23 +342,22 @@ fn is_square_deadlock(
     ];
 
     for (dr, dc) in quadrants {
+        let vertical = grid.step(box_pos, dr, 0);
+        let horizontal = grid.step(box_pos, 0, dc);
+        let diagonal = grid.step(box_pos, dr, dc);
+
         // Check the other 3 corners of this specific 2x2 quadrant: adjacent vertical, horizontal, diagonal.
-        if is_free(box_row + dr, box_col, boxes, grid)
-            || is_free(box_row, box_col + dc, boxes, grid)
-            || is_free(box_row + dr, box_col + dc, boxes, grid)
+        if is_free(vertical, boxes, grid)
+            || is_free(horizontal, boxes, grid)
+            || is_free(diagonal, boxes, grid)
         {
             continue;
         }
 
         // We check if *any* of the 4 positions in this 2x2 is a box strictly outside a goal.
-        let is_dead_quadrant = [
-            (box_row, box_col),
-            (box_row + dr, box_col),
-            (box_row, box_col + dc),
-            (box_row + dr, box_col + dc),
-        ]
-        .iter()
-        .any(|p| boxes.contains(p) && !goals.contains(p));
+        let is_dead_quadrant = [box_pos, vertical, horizontal, diagonal]
+            .iter()
+            .any(|p| boxes.contains(p) && !goals.contains(p));
 
         if is_dead_quadrant {
             return true;
@@ -438,17 +376,15 @@ fn mark_reachable(
     stack: &mut Vec<Pos>,
 ) {
     stack.push(start);
-    reachable[start.0 as usize][start.1 as usize] = true;
+    reachable[start] = true;
 
-    while let Some((row, col)) = stack.pop() {
+    while let Some(pos) = stack.pop() {
         for &(dr, dc, _) in &DIRECTIONS {
-            let new_row = row + dr;
-            let new_col = col + dc;
-            let (ur, uc) = (new_row as usize, new_col as usize);
+            let next = grid.step(pos, dr, dc);
 
-            if is_free(new_row, new_col, boxes, grid) && !reachable[ur][uc] {
-                reachable[ur][uc] = true;
-                stack.push((new_row, new_col));
+            if is_free(next, boxes, grid) && !reachable[next] {
+                reachable[next] = true;
+                stack.push(next);
             }
         }
     }
@@ -464,13 +400,11 @@ fn mark_reachable_with_path(
     queue: &mut VecDeque<Pos>,
 ) {
     queue.push_back(start);
-    reachable[start.0 as usize][start.1 as usize] = true;
+    reachable[start] = true;
 
-    while let Some((row, col)) = queue.pop_front() {
+    while let Some(pos) = queue.pop_front() {
         for &(dr, dc, push_ch) in &DIRECTIONS {
-            let new_row = row + dr;
-            let new_col = col + dc;
-            let (ur, uc) = (new_row as usize, new_col as usize);
+            let next = grid.step(pos, dr, dc);
 
             // Path character is the direction *from* new_pos *to* old_pos
             let path_ch = match push_ch {
@@ -481,10 +415,10 @@ fn mark_reachable_with_path(
                 _ => continue,
             };
 
-            if is_free(new_row, new_col, boxes, grid) && !reachable[ur][uc] {
-                reachable[ur][uc] = true;
-                came_from[ur][uc] = path_ch;
-                queue.push_back((new_row, new_col));
+            if is_free(next, boxes, grid) && !reachable[next] {
+                reachable[next] = true;
+                came_from[next] = path_ch;
+                queue.push_back(next);
             }
         }
     }
@@ -500,10 +434,8 @@ fn get_path(end: Pos, start: Pos, came_from: &PathGrid, grid: &Grid) -> SmallVec
     let mut current = end;
 
     // Follow the came_from links back to the start
-    let max_len = grid.len() * grid[0].len();
-    for _ in 0..max_len {
-        let (cr, cc) = current;
-        let dir_char = came_from[cr as usize][cc as usize];
+    for _ in 0..grid.len() {
+        let dir_char = came_from[current];
 
         if dir_char == 0 {
             break; // Reached start or uninitialized square
@@ -513,10 +445,10 @@ fn get_path(end: Pos, start: Pos, came_from: &PathGrid, grid: &Grid) -> SmallVec
 
         // Determine the previous position
         current = match dir_char {
-            b'u' => (cr + 1, cc), // `current` came from Up, so the previous position is Dow
-            b'd' => (cr - 1, cc), // `current` came from Down, so the previous position is Up
-            b'l' => (cr, cc + 1), // `current` came from Left, so the previous position is Right
-            b'r' => (cr, cc - 1), // `current` came from Right, so the previous position is Left
+            b'u' => grid.step(current, 1, 0), // `current` came from Up, so the previous position is Down
+            b'd' => grid.step(current, -1, 0), // `current` came from Down, so the previous position is Up
+            b'l' => grid.step(current, 0, 1), // `current` came from Left, so the previous position is Right
+            b'r' => grid.step(current, 0, -1), // `current` came from Right, so the previous position is Left
             _ => break,
         };
 
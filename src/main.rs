@@ -1,4 +1,4 @@
-use sokoban_solver::solve;
+use sokoban_solver::solve_detailed;
 
 // num_branch: 12_888_343
 // lUrRlllluurrDulldRdRRlluurDldRdDllddrrUUddlUruUddlluRdrUrurDrrddllUdrruulLLrddrUruLLuURUruulDlDDuurrdLdlDuruulDDurrrdLL
@@ -20,8 +20,18 @@ fn main() {
         "##   #   ##",
         " #########",
     ];
-    let solution = solve(microban92).expect("No solution!");
-    println!("{}", solution);
+    let solution = solve_detailed(microban92).expect("No solution!");
+    let stats = &solution.stats;
+    eprintln!(
+        "expanded: {}, generated: {}, duplicates: {}, deadlocks: {}, peak open: {}, elapsed: {:?}",
+        stats.nodes_expanded,
+        stats.nodes_generated,
+        stats.duplicates_pruned,
+        stats.deadlocks.total(),
+        stats.peak_open,
+        stats.elapsed
+    );
+    println!("{}", solution.path);
 }
 
 // // Takes around 25 seconds to solve
@@ -0,0 +1,123 @@
+use ahash::AHashMap;
+
+use crate::distance::{compute_push_distance_map, side_blocks};
+use crate::{BoxOrGoal, DistanceMap, Level, is_frozen};
+
+// Frozen layouts whose goal reachability is kept before the cache starts over. Each holds a
+// distance map per free goal.
+const MAX_CACHED: usize = 256;
+
+// Where boxes can still be pushed to once some boxes are frozen on their goals for good.
+struct Reach {
+    goals: BoxOrGoal,       // The goals not taken by a frozen box
+    maps: Vec<DistanceMap>, // Push distances towards each of `goals`, frozen boxes as walls
+}
+
+impl Reach {
+    fn new(level: &Level, frozen: &BoxOrGoal) -> Reach {
+        let mut grid = level.grid.clone();
+        for &pos in frozen {
+            grid[pos] = b'#';
+        }
+        let blocks = side_blocks(&grid);
+        let goals: BoxOrGoal = level
+            .goals
+            .iter()
+            .copied()
+            .filter(|goal| !frozen.contains(goal))
+            .collect();
+        let maps = goals
+            .iter()
+            .map(|&goal| compute_push_distance_map(goal, &grid, &blocks))
+            .collect();
+        Reach { goals, maps }
+    }
+}
+
+// The static goal distances assume every other box gets out of the way. Boxes frozen on
+// goals never will, so they can cut other boxes off from the remaining goals. This check
+// recomputes the reachability around them and looks for a perfect box-to-goal matching.
+pub(crate) struct GoalMatching {
+    cache: AHashMap<BoxOrGoal, Reach>, // By sorted frozen boxes
+    walls: BoxOrGoal,
+    frozen: BoxOrGoal,
+}
+
+impl GoalMatching {
+    pub(crate) fn new() -> GoalMatching {
+        GoalMatching {
+            cache: AHashMap::new(),
+            walls: BoxOrGoal::new(),
+            frozen: BoxOrGoal::new(),
+        }
+    }
+
+    // Whether every box not frozen can still reach a goal of its own.
+    pub(crate) fn has_matching(&mut self, boxes: &BoxOrGoal, level: &Level) -> bool {
+        let goals = &level.goals;
+        let mut frozen = BoxOrGoal::new();
+        // Only boxes on goals matter, a box frozen elsewhere was a freeze deadlock already.
+        for &pos in boxes.iter().filter(|pos| goals.contains(pos)) {
+            if frozen.contains(&pos) {
+                continue;
+            }
+            self.walls.clear();
+            self.frozen.clear();
+            if is_frozen(pos, boxes, level, &mut self.walls, &mut self.frozen) {
+                for &pos in &self.frozen {
+                    if !frozen.contains(&pos) {
+                        frozen.push(pos);
+                    }
+                }
+            }
+        }
+        if frozen.is_empty() {
+            return true;
+        }
+        if frozen.iter().any(|pos| !goals.contains(pos)) {
+            return false;
+        }
+        frozen.sort_unstable();
+
+        if self.cache.len() >= MAX_CACHED && !self.cache.contains_key(&frozen) {
+            self.cache.clear();
+        }
+        let reach = self
+            .cache
+            .entry(frozen.clone())
+            .or_insert_with(|| Reach::new(level, &frozen));
+
+        let free: BoxOrGoal = boxes
+            .iter()
+            .copied()
+            .filter(|pos| !frozen.contains(pos))
+            .collect();
+        let mut owner = vec![usize::MAX; reach.goals.len()]; // Box matched to each goal
+        (0..free.len()).all(|b| {
+            let mut seen = vec![false; reach.goals.len()];
+            augment(b, &free, &reach.maps, &mut owner, &mut seen)
+        })
+    }
+}
+
+// Kuhn's augmenting path step: finds a goal for box `b`, moving earlier boxes to other goals
+// they can reach when needed.
+fn augment(
+    b: usize,
+    boxes: &BoxOrGoal,
+    maps: &[DistanceMap],
+    owner: &mut [usize],
+    seen: &mut [bool],
+) -> bool {
+    for g in 0..maps.len() {
+        if seen[g] || maps[g][boxes[b]] == u16::MAX {
+            continue;
+        }
+        seen[g] = true;
+        if owner[g] == usize::MAX || augment(owner[g], boxes, maps, owner, seen) {
+            owner[g] = b;
+            return true;
+        }
+    }
+    false
+}
@@ -0,0 +1,27 @@
+use crate::SearchStats;
+
+// Live numbers handed to `SearchObserver::on_progress`.
+#[derive(Clone, Debug)]
+pub struct Progress<'a> {
+    pub stats: &'a SearchStats, // `elapsed` and `memory_bytes` are refreshed just before the call
+    pub open_len: usize,        // States waiting in the A* queue
+    pub visited_len: usize,     // Distinct states seen so far
+    pub f_bound: usize,         // Priority (pushes + estimate) of the state being expanded
+}
+
+// Hooks into a running search, e.g. to drive a dashboard. Every method defaults to a no-op,
+// so implement only what you need and pass it to `solve_observed`.
+pub trait SearchObserver {
+    // Called for every state popped from the queue, with its pushes so far and priority.
+    fn on_expand(&mut self, _cost: usize, _priority: usize) {}
+
+    // Called whenever the priority of the expanded states rises above every earlier one.
+    // With an admissible heuristic no solution can use fewer pushes than `bound`.
+    fn on_new_best_lower_bound(&mut self, _bound: usize) {}
+
+    // Called every `SolverConfig::progress_interval` expansions.
+    fn on_progress(&mut self, _progress: &Progress) {}
+}
+
+// The observer used by `solve_with`, ignores everything.
+impl SearchObserver for () {}
@@ -0,0 +1,168 @@
+use std::fmt;
+use std::ops::AddAssign;
+use std::time::Duration;
+
+use crate::Solution;
+
+// What a search cost, whether it found a solution or not.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    pub nodes_expanded: usize,    // States popped from the A* queue
+    pub nodes_generated: usize,   // Children that made it into the A* queue
+    pub duplicates_pruned: usize, // Children already visited at the same or a lower cost
+    pub deadlocks: DeadlockStats,
+    pub peak_open: usize,          // Largest size of the A* queue
+    pub peak_visited: usize,       // Largest number of distinct states seen
+    pub evictions: usize,          // Transposition table entries overwritten by newer states
+    pub open_pruned: usize,        // States dropped from a full open list
+    pub elapsed: Duration,         // Wall-clock time spent in the search
+    pub memory_bytes: usize,       // Rough size of the open list and visited set at the end
+    pub threads: Vec<ThreadStats>, // One per thread of `Algorithm::ParallelAStar`, else empty
+}
+
+// Adds up searches that ran side by side, like the threads of a parallel search: their peaks
+// and memory add up too, `elapsed` is the longer of the two.
+impl AddAssign<&SearchStats> for SearchStats {
+    fn add_assign(&mut self, other: &SearchStats) {
+        // Destructured so a new field cannot be left out.
+        let SearchStats {
+            nodes_expanded,
+            nodes_generated,
+            duplicates_pruned,
+            deadlocks,
+            peak_open,
+            peak_visited,
+            evictions,
+            open_pruned,
+            elapsed,
+            memory_bytes,
+            threads,
+        } = other;
+        self.nodes_expanded += nodes_expanded;
+        self.nodes_generated += nodes_generated;
+        self.duplicates_pruned += duplicates_pruned;
+        self.deadlocks += deadlocks;
+        self.peak_open += peak_open;
+        self.peak_visited += peak_visited;
+        self.evictions += evictions;
+        self.open_pruned += open_pruned;
+        self.elapsed = self.elapsed.max(*elapsed);
+        self.memory_bytes += memory_bytes;
+        self.threads.extend_from_slice(threads);
+    }
+}
+
+// The share of a parallel search one thread handled, to see how evenly the states spread.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ThreadStats {
+    pub nodes_expanded: usize,
+    pub nodes_generated: usize,
+    pub states_received: usize, // Children generated by other threads and sent over
+    pub peak_open: usize,
+}
+
+// Children thrown away because their box layout can no longer be solved, by check.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DeadlockStats {
+    pub dead_square: usize, // Box pushed onto a square it can never leave towards a goal
+    pub frozen: usize,      // Box that can no longer move, or froze another one, off a goal
+    pub square: usize,      // `is_square_deadlock`: 2x2 block of boxes and walls
+    pub segment: usize,     // More boxes on a wall segment between two corners than goals
+    pub diagonal: usize,    // Closed diagonal, two boxes that can only go into a dead corner
+    pub corral: usize,      // Expanded states with a PI-corral that can never be opened
+    pub bipartite: usize,   // Boxes frozen on goals cut the others off from the free goals
+    pub unmatched: usize,   // Boxes cannot all be matched to distinct reachable goals
+    pub pattern: usize,     // Contains a deadlock learned earlier, see `PatternDatabase`
+}
+
+impl DeadlockStats {
+    pub fn total(&self) -> usize {
+        self.dead_square
+            + self.frozen
+            + self.square
+            + self.segment
+            + self.diagonal
+            + self.corral
+            + self.bipartite
+            + self.unmatched
+            + self.pattern
+    }
+}
+
+impl AddAssign<&DeadlockStats> for DeadlockStats {
+    fn add_assign(&mut self, other: &DeadlockStats) {
+        let DeadlockStats {
+            dead_square,
+            frozen,
+            square,
+            segment,
+            diagonal,
+            corral,
+            bipartite,
+            unmatched,
+            pattern,
+        } = other;
+        self.dead_square += dead_square;
+        self.frozen += frozen;
+        self.square += square;
+        self.segment += segment;
+        self.diagonal += diagonal;
+        self.corral += corral;
+        self.bipartite += bipartite;
+        self.unmatched += unmatched;
+        self.pattern += pattern;
+    }
+}
+
+// The `SolverConfig` limit that stopped a search.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+    Nodes,
+    Time,
+    Memory,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::Nodes => write!(f, "node limit"),
+            Limit::Time => write!(f, "time limit"),
+            Limit::Memory => write!(f, "memory limit"),
+        }
+    }
+}
+
+// `Unsolvable` means the whole state space was explored, while `LimitReached` and
+// `Cancelled` only mean the search stopped before it could tell either way.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SolveOutcome {
+    Solved(Solution),
+    Unsolvable { stats: SearchStats },
+    LimitReached { limit: Limit, stats: SearchStats },
+    Cancelled { stats: SearchStats },
+}
+
+impl SolveOutcome {
+    pub fn solution(&self) -> Option<&Solution> {
+        match self {
+            SolveOutcome::Solved(solution) => Some(solution),
+            _ => None,
+        }
+    }
+
+    pub fn into_solution(self) -> Option<Solution> {
+        match self {
+            SolveOutcome::Solved(solution) => Some(solution),
+            _ => None,
+        }
+    }
+
+    pub fn stats(&self) -> &SearchStats {
+        match self {
+            SolveOutcome::Solved(solution) => &solution.stats,
+            SolveOutcome::Unsolvable { stats }
+            | SolveOutcome::LimitReached { stats, .. }
+            | SolveOutcome::Cancelled { stats } => stats,
+        }
+    }
+}
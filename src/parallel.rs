@@ -0,0 +1,374 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::search::{Frontier, solution_from};
+use crate::{
+    BoxOrGoal, LIMIT_CHECK_INTERVAL, Level, Limit, Node, Pos, Progress, SearchObserver,
+    SearchStats, SolveOutcome, SolverConfig, State, ThreadStats,
+};
+
+// Children bound for another thread are sent once this many have piled up, or when the
+// sending thread runs out of work.
+const BATCH: usize = 64;
+// How long an idle thread waits for states before checking whether the search is over.
+const IDLE_WAIT: Duration = Duration::from_micros(200);
+// How often the calling thread looks at the workers to report progress.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+// A state handed to the thread that owns it.
+struct Message {
+    boxes: BoxOrGoal,
+    player: Pos,
+    hash: u64,
+    cost: u16,
+    push: Node,
+}
+
+enum Stop {
+    Limit(Limit),
+    Cancelled,
+}
+
+// What the threads share. `work` counts the states that are queued or on their way to a
+// queue; once it drops to zero every reachable state has been expanded.
+struct Shared {
+    work: AtomicUsize,
+    expanded: AtomicUsize,
+    stop: AtomicBool,
+    stopped: Mutex<Option<Stop>>,
+    best_cost: AtomicU16,
+    found: Mutex<Option<(u16, u32)>>, // Cost and node id of the best goal so far
+    loads: Vec<Load>,
+}
+
+// Numbers a thread publishes every `LIMIT_CHECK_INTERVAL` expansions.
+#[derive(Default)]
+struct Load {
+    memory_bytes: AtomicUsize,
+    open_len: AtomicUsize,
+    visited_len: AtomicUsize,
+}
+
+impl Shared {
+    fn memory_bytes(&self) -> usize {
+        let loads = self.loads.iter();
+        loads
+            .map(|load| load.memory_bytes.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    fn stop(&self, reason: Stop) {
+        self.stopped.lock().unwrap().get_or_insert(reason);
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    fn is_over(&self) -> bool {
+        self.stop.load(Ordering::Relaxed) || self.work.load(Ordering::Acquire) == 0
+    }
+}
+
+// One thread of the search: owns the states whose box layout hashes to `index`, expands them
+// and sends their children to the threads that own those.
+struct Worker<'a> {
+    index: usize,
+    frontier: Frontier<'a>,
+    config: &'a SolverConfig,
+    shared: &'a Shared,
+    inbox: Receiver<Vec<Message>>,
+    outboxes: Vec<Vec<Message>>,
+    senders: Vec<Sender<Vec<Message>>>,
+    stats: SearchStats,
+    received: usize,
+    start_time: Instant,
+}
+
+// Node ids interleave the arenas of the threads, so every id names one node of one thread.
+// `None` once the arenas hold more nodes than such ids can tell apart.
+fn node_id(index: usize, node: u32, threads: usize) -> Option<u32> {
+    u32::try_from(node as u64 * threads as u64 + index as u64).ok()
+}
+
+fn owner(hash: u64, threads: usize) -> usize {
+    // The visited tables index by the low bits, keep them spread within each thread.
+    (hash >> 40) as usize % threads
+}
+
+// Hash-distributed A*: each thread runs its own open list and visited set over the states it
+// owns. Without an admissible heuristic the first goal wins, otherwise the threads keep going
+// until no open state could lead to fewer pushes.
+pub(crate) fn solve_parallel<O: SearchObserver + ?Sized>(
+    level: &Level,
+    config: &SolverConfig,
+    observer: &mut O,
+) -> SolveOutcome {
+    let start_time = Instant::now();
+    let threads = config.thread_count();
+
+    let root = Frontier::forward(level, config);
+    let root_owner = owner(root.queue.peek().unwrap().hash, threads);
+    let mut frontiers: Vec<Option<Frontier>> = (0..threads)
+        .map(|index| (index != root_owner).then(|| Frontier::partition(level, config)))
+        .collect();
+    frontiers[root_owner] = Some(root);
+
+    let shared = Shared {
+        work: AtomicUsize::new(1),
+        expanded: AtomicUsize::new(0),
+        stop: AtomicBool::new(false),
+        stopped: Mutex::new(None),
+        best_cost: AtomicU16::new(u16::MAX),
+        found: Mutex::new(None),
+        loads: (0..threads).map(|_| Load::default()).collect(),
+    };
+    let (senders, inboxes): (Vec<_>, Vec<_>) = (0..threads).map(|_| mpsc::channel()).unzip();
+
+    let results: Vec<(Vec<Node>, SearchStats, usize)> = thread::scope(|scope| {
+        let handles: Vec<_> = frontiers
+            .into_iter()
+            .zip(inboxes)
+            .enumerate()
+            .map(|(index, (frontier, inbox))| {
+                let worker = Worker {
+                    index,
+                    frontier: frontier.unwrap(),
+                    config,
+                    shared: &shared,
+                    inbox,
+                    outboxes: (0..threads).map(|_| Vec::new()).collect(),
+                    senders: senders.clone(),
+                    stats: SearchStats::default(),
+                    received: 0,
+                    start_time,
+                };
+                scope.spawn(move || worker.run())
+            })
+            .collect();
+
+        // The observer stays on this thread, it only hears about progress.
+        let mut next_report = config.progress_interval;
+        while !handles.iter().all(|handle| handle.is_finished()) {
+            thread::sleep(POLL_INTERVAL);
+            let expanded = shared.expanded.load(Ordering::Relaxed);
+            if expanded >= next_report {
+                next_report = (expanded / config.progress_interval + 1) * config.progress_interval;
+                let loads = shared.loads.iter();
+                let stats = SearchStats {
+                    nodes_expanded: expanded,
+                    elapsed: start_time.elapsed(),
+                    memory_bytes: shared.memory_bytes(),
+                    ..SearchStats::default()
+                };
+                observer.on_progress(&Progress {
+                    stats: &stats,
+                    open_len: loads
+                        .clone()
+                        .map(|load| load.open_len.load(Ordering::Relaxed))
+                        .sum(),
+                    visited_len: loads
+                        .map(|load| load.visited_len.load(Ordering::Relaxed))
+                        .sum(),
+                    f_bound: 0,
+                });
+            }
+        }
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    let mut stats = SearchStats::default();
+    let mut arenas = Vec::with_capacity(threads);
+    for (nodes, thread, received) in results {
+        stats += &thread;
+        stats.threads.push(ThreadStats {
+            nodes_expanded: thread.nodes_expanded,
+            nodes_generated: thread.nodes_generated,
+            states_received: received,
+            peak_open: thread.peak_open,
+        });
+        arenas.push(nodes);
+    }
+    stats.elapsed = start_time.elapsed();
+
+    if let Some((_, last)) = *shared.found.lock().unwrap() {
+        let pushes = joined_arenas(&arenas, last);
+        let last = pushes.len() as u32 - 1;
+        return SolveOutcome::Solved(solution_from(level, &pushes, last, stats));
+    }
+    match shared.stopped.into_inner().unwrap() {
+        Some(Stop::Limit(limit)) => SolveOutcome::LimitReached { limit, stats },
+        Some(Stop::Cancelled) => SolveOutcome::Cancelled { stats },
+        None => SolveOutcome::Unsolvable { stats },
+    }
+}
+
+// The pushes leading to node `last` as a chain `rebuild_path` can replay, the root first.
+// The root is the first node in the arena of its thread, so its id is below the thread count.
+fn joined_arenas(arenas: &[Vec<Node>], last: u32) -> Vec<Node> {
+    let threads = arenas.len();
+    let mut pushes = Vec::new();
+    let mut current = last as usize;
+    while current >= threads {
+        let node = arenas[current % threads][current / threads];
+        pushes.push(node);
+        current = node.parent as usize;
+    }
+    pushes.push(arenas[current][0]);
+    pushes.reverse();
+
+    for (index, push) in pushes.iter_mut().enumerate().skip(1) {
+        push.parent = index as u32 - 1;
+    }
+    pushes
+}
+
+impl Worker<'_> {
+    fn run(mut self) -> (Vec<Node>, SearchStats, usize) {
+        let shared = self.shared;
+        while !shared.stop.load(Ordering::Relaxed) {
+            while let Ok(messages) = self.inbox.try_recv() {
+                self.receive(messages);
+            }
+
+            let Some(state) = self.frontier.queue.pop() else {
+                // Others may be waiting for what piled up here.
+                self.flush(0);
+                if shared.is_over() {
+                    break;
+                }
+                match self.inbox.recv_timeout(IDLE_WAIT) {
+                    Ok(messages) => self.receive(messages),
+                    Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => {}
+                }
+                continue;
+            };
+
+            // With an admissible heuristic nothing below this state can beat the best goal.
+            if state.priority < shared.best_cost.load(Ordering::Relaxed) {
+                self.step(&state);
+            }
+            self.flush(BATCH);
+            shared.work.fetch_sub(1, Ordering::AcqRel);
+        }
+
+        self.update_load();
+        self.stats.memory_bytes = self.frontier.memory_bytes();
+        self.stats.peak_visited = self.frontier.visited.len();
+        self.stats.evictions = self.frontier.visited.evictions();
+        (self.frontier.nodes, self.stats, self.received)
+    }
+
+    fn step(&mut self, state: &State) {
+        let shared = self.shared;
+        let threads = self.senders.len();
+        self.stats.nodes_expanded += 1;
+        let expanded = shared.expanded.fetch_add(1, Ordering::Relaxed) + 1;
+        let index = self.index;
+        // Parent links of its children and the goal both refer to the state by its id.
+        let Some(id) = node_id(index, state.node, threads) else {
+            shared.stop(Stop::Limit(Limit::Memory));
+            return;
+        };
+
+        if self.frontier.is_target(state) {
+            let mut found = shared.found.lock().unwrap();
+            if found.is_none_or(|(cost, _)| state.cost < cost) {
+                *found = Some((state.cost, id));
+                shared.best_cost.store(state.cost, Ordering::Relaxed);
+            }
+            if !self.config.heuristic.is_admissible() {
+                shared.stop.store(true, Ordering::Relaxed);
+            }
+            return;
+        }
+
+        if self
+            .stats
+            .nodes_expanded
+            .is_multiple_of(LIMIT_CHECK_INTERVAL)
+        {
+            self.update_load();
+        }
+        if self.config.is_cancelled() {
+            shared.stop(Stop::Cancelled);
+            return;
+        }
+        let totals = SearchStats {
+            nodes_expanded: expanded,
+            elapsed: self.start_time.elapsed(),
+            memory_bytes: shared.memory_bytes(),
+            ..SearchStats::default()
+        };
+        if let Some(limit) = self.config.exceeded(&totals) {
+            shared.stop(Stop::Limit(limit));
+            return;
+        }
+
+        let cost = state.cost + 1;
+        let outboxes = &mut self.outboxes;
+        self.frontier.for_each_child(
+            state,
+            &mut self.stats,
+            |frontier, boxes, hash, player, push, stats| {
+                let push = Node { parent: id, ..push };
+                let to = owner(hash, threads);
+                if to == index {
+                    if frontier.add_child(boxes, hash, player, cost, push, stats) {
+                        shared.work.fetch_add(1, Ordering::AcqRel);
+                    }
+                } else {
+                    shared.work.fetch_add(1, Ordering::AcqRel);
+                    outboxes[to].push(Message {
+                        boxes,
+                        player,
+                        hash,
+                        cost,
+                        push,
+                    });
+                }
+            },
+        );
+    }
+
+    // Queues the states another thread sent over, each one is counted in `work` until then.
+    fn receive(&mut self, messages: Vec<Message>) {
+        let best_cost = self.shared.best_cost.load(Ordering::Relaxed);
+        for message in messages {
+            self.received += 1;
+            let queued = message.cost < best_cost
+                && self.frontier.add_child(
+                    message.boxes,
+                    message.hash,
+                    message.player,
+                    message.cost,
+                    message.push,
+                    &mut self.stats,
+                );
+            if queued {
+                self.shared.work.fetch_add(1, Ordering::AcqRel);
+            }
+            self.shared.work.fetch_sub(1, Ordering::AcqRel);
+        }
+    }
+
+    // Sends every outbox holding at least `min` states.
+    fn flush(&mut self, min: usize) {
+        for (to, outbox) in self.outboxes.iter_mut().enumerate() {
+            if !outbox.is_empty() && outbox.len() >= min {
+                // A thread only hangs up once the search is over.
+                let _ = self.senders[to].send(std::mem::take(outbox));
+            }
+        }
+    }
+
+    fn update_load(&self) {
+        let load = &self.shared.loads[self.index];
+        let frontier = &self.frontier;
+        load.memory_bytes
+            .store(frontier.memory_bytes(), Ordering::Relaxed);
+        load.open_len.store(frontier.queue.len(), Ordering::Relaxed);
+        load.visited_len
+            .store(frontier.visited.len(), Ordering::Relaxed);
+    }
+}
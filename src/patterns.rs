@@ -0,0 +1,261 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use ahash::AHashSet;
+use serde::{Deserialize, Serialize};
+
+use crate::grid::CellMap;
+use crate::{BoolGrid, BoxOrGoal, Level, Pos, mark_reachable};
+
+// A box layout proven unsolvable: seen from the side of `player`, the boxes fence off a corral
+// around `corral` that can neither be opened nor settled. Other boxes cannot help, as long as
+// none of them sits inside the corral.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct Deadlock {
+    pub(crate) boxes: BoxOrGoal, // Sorted
+    pub(crate) player: Pos,
+    pub(crate) corral: Pos,
+}
+
+// Deadlocks learned by the searches this database was given, kept per layout of walls and
+// goals so that later runs on the same level start out knowing them. Clones share the same
+// patterns, so keep one to `save` after the solves.
+#[derive(Clone, Debug, Default)]
+pub struct PatternDatabase(Arc<Mutex<Store>>);
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Store {
+    levels: Vec<StoredLevel>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredLevel {
+    layout: Vec<String>, // Rows of walls `#`, goals `.` and floor, without boxes nor player
+    deadlocks: Vec<StoredDeadlock>,
+}
+
+// Squares as (row, column).
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct StoredDeadlock {
+    boxes: Vec<(usize, usize)>,
+    player: (usize, usize),
+    corral: (usize, usize),
+}
+
+impl PatternDatabase {
+    pub fn new() -> PatternDatabase {
+        PatternDatabase::default()
+    }
+
+    // Reads a database written by `save`.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<PatternDatabase> {
+        let json = fs::read_to_string(path)?;
+        let store: Store = serde_json::from_str(&json)?;
+        Ok(PatternDatabase(Arc::new(Mutex::new(store))))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let json = serde_json::to_string(&*self.store())?;
+        fs::write(path, json)
+    }
+
+    // Deadlocks over all levels.
+    pub fn len(&self) -> usize {
+        let store = self.store();
+        store.levels.iter().map(|level| level.deadlocks.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // A search that panicked while holding the lock only ever leaves whole entries behind.
+    fn store(&self) -> MutexGuard<'_, Store> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+// The deadlocks one search knows of, indexed by box square. Learned ones go to the database
+// right away, and those other searches put there since are picked up on the way.
+pub(crate) struct DeadlockPatterns {
+    known: Vec<Deadlock>,
+    seen: AHashSet<Deadlock>,
+    by_square: CellMap<Vec<u32>>, // Indexes into `known` of the deadlocks with a box there
+    database: Option<PatternDatabase>,
+    layout: Vec<String>,
+    synced: usize, // Deadlocks of the level's database entry already in `known`
+    reachable: BoolGrid,
+    stack: Vec<Pos>,
+}
+
+impl DeadlockPatterns {
+    pub(crate) fn new(level: &Level, database: Option<&PatternDatabase>) -> DeadlockPatterns {
+        let grid = &level.grid;
+        let layout = match database {
+            Some(_) => layout(level),
+            None => Vec::new(),
+        };
+        let mut patterns = DeadlockPatterns {
+            known: Vec::new(),
+            seen: AHashSet::new(),
+            by_square: grid.map(Vec::new()),
+            database: database.cloned(),
+            layout,
+            synced: 0,
+            reachable: grid.map(false),
+            stack: Vec::new(),
+        };
+        patterns.sync(None, level);
+        patterns
+    }
+
+    pub(crate) fn learn(&mut self, deadlock: Deadlock, level: &Level) {
+        if self.seen.contains(&deadlock) {
+            return;
+        }
+        if self.database.is_some() {
+            self.sync(Some(&deadlock), level);
+        }
+        self.add(deadlock);
+    }
+
+    // Whether `boxes`, with the player at `player` and a box just pushed to `moved`, contain
+    // a known deadlock involving that box.
+    pub(crate) fn is_dead(
+        &mut self,
+        boxes: &BoxOrGoal,
+        player: Pos,
+        moved: Pos,
+        level: &Level,
+    ) -> bool {
+        let grid = &level.grid;
+        for k in 0..self.by_square[moved].len() {
+            let deadlock = &self.known[self.by_square[moved][k] as usize];
+            if !deadlock.boxes.iter().all(|pos| boxes.contains(pos)) {
+                continue;
+            }
+
+            self.reachable.fill(false);
+            self.stack.clear();
+            mark_reachable(
+                deadlock.corral,
+                &deadlock.boxes,
+                grid,
+                &mut self.reachable,
+                &mut self.stack,
+            );
+            if self.reachable[player] || boxes.iter().any(|&pos| self.reachable[pos]) {
+                continue;
+            }
+
+            self.reachable.fill(false);
+            mark_reachable(
+                player,
+                &deadlock.boxes,
+                grid,
+                &mut self.reachable,
+                &mut self.stack,
+            );
+            if self.reachable[deadlock.player] {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn add(&mut self, deadlock: Deadlock) {
+        if !self.seen.insert(deadlock.clone()) {
+            return;
+        }
+        let index = self.known.len() as u32;
+        for &pos in &deadlock.boxes {
+            self.by_square[pos].push(index);
+        }
+        self.known.push(deadlock);
+    }
+
+    // Stores `learned` in the database, if any, and takes over the level's deadlocks found
+    // since the last call.
+    fn sync(&mut self, learned: Option<&Deadlock>, level: &Level) {
+        let Some(database) = &self.database else {
+            return;
+        };
+        let grid = &level.grid;
+        let mut store = database.store();
+        let index = match store.levels.iter().position(|l| l.layout == self.layout) {
+            Some(index) => index,
+            None => {
+                store.levels.push(StoredLevel {
+                    layout: self.layout.clone(),
+                    deadlocks: Vec::new(),
+                });
+                store.levels.len() - 1
+            }
+        };
+        let deadlocks = &mut store.levels[index].deadlocks;
+        if let Some(deadlock) = learned {
+            let stored = StoredDeadlock {
+                boxes: deadlock.boxes.iter().map(|&pos| grid.coords(pos)).collect(),
+                player: grid.coords(deadlock.player),
+                corral: grid.coords(deadlock.corral),
+            };
+            if !deadlocks.contains(&stored) {
+                deadlocks.push(stored);
+            }
+        }
+
+        let on_floor = |&(row, col): &(usize, usize)| {
+            row < grid.height() && col < grid.width() && !grid.is_wall(grid.pos(row, col))
+        };
+        let new: Vec<Deadlock> = deadlocks[self.synced..]
+            .iter()
+            // A hand-edited or stale file could point at a wall or outside the level.
+            .filter(|stored| {
+                stored.boxes.iter().all(on_floor)
+                    && on_floor(&stored.player)
+                    && on_floor(&stored.corral)
+            })
+            .map(|stored| {
+                let mut boxes: BoxOrGoal = stored
+                    .boxes
+                    .iter()
+                    .map(|&(row, col)| grid.pos(row, col))
+                    .collect();
+                boxes.sort_unstable();
+                Deadlock {
+                    boxes,
+                    player: grid.pos(stored.player.0, stored.player.1),
+                    corral: grid.pos(stored.corral.0, stored.corral.1),
+                }
+            })
+            .collect();
+        self.synced = deadlocks.len();
+        drop(store);
+        for deadlock in new {
+            self.add(deadlock);
+        }
+    }
+}
+
+// What a deadlock depends on besides the boxes: the walls and goals of the level.
+fn layout(level: &Level) -> Vec<String> {
+    let grid = &level.grid;
+    (0..grid.height())
+        .map(|row| {
+            (0..grid.width())
+                .map(|col| {
+                    let pos = grid.pos(row, col);
+                    if grid.is_wall(pos) {
+                        '#'
+                    } else if level.goals.contains(&pos) {
+                        '.'
+                    } else {
+                        ' '
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
@@ -0,0 +1,156 @@
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{Algorithm, CancelToken, Level, SolveOutcome, SolverConfig, solve_with};
+
+// Strategies for `solve_portfolio` to race against each other. `Portfolio::default()` runs
+// forward A*, greedy best-first and the reverse search.
+#[derive(Clone, Debug)]
+pub struct Portfolio {
+    strategies: Vec<(String, SolverConfig)>,
+    deadline: Option<Duration>,
+    cancel: Option<CancelToken>,
+}
+
+impl Default for Portfolio {
+    fn default() -> Self {
+        Portfolio::new()
+            .strategy("forward", SolverConfig::new())
+            .strategy(
+                "greedy",
+                SolverConfig::new().algorithm(Algorithm::GreedyBestFirst),
+            )
+            .strategy("reverse", SolverConfig::new().algorithm(Algorithm::Reverse))
+    }
+}
+
+impl Portfolio {
+    // An empty portfolio, add strategies with `strategy`.
+    pub fn new() -> Portfolio {
+        Portfolio {
+            strategies: Vec::new(),
+            deadline: None,
+            cancel: None,
+        }
+    }
+
+    // Runs `config` on a thread of its own. A cancel token set on `config` still stops this
+    // strategy, the portfolio only links it to a token of its own to stop the strategy once
+    // it has its answer.
+    pub fn strategy(mut self, name: impl Into<String>, config: SolverConfig) -> Portfolio {
+        self.strategies.push((name.into(), config));
+        self
+    }
+
+    // Instead of taking the first solution, let the strategies run for this long and keep
+    // the one with the fewest pushes, then moves. Strategies still running are cancelled.
+    pub fn best_within(mut self, deadline: Duration) -> Portfolio {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    // Stops every strategy, and with them `solve_portfolio`, once `token` is cancelled.
+    pub fn cancel_token(mut self, token: CancelToken) -> Portfolio {
+        self.cancel = Some(token);
+        self
+    }
+}
+
+// What `solve_portfolio` settled on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PortfolioOutcome {
+    pub winner: Option<String>, // Name of the strategy whose solution `outcome` holds
+    pub outcome: SolveOutcome,
+}
+
+// Races the strategies of `portfolio` on separate threads and cancels the rest once it has
+// its answer. The first strategy to prove the level `Unsolvable` ends the race as well.
+// Without either, the outcome is that of the first strategy. Panics if the portfolio has no
+// strategies.
+pub fn solve_portfolio(level: &Level, portfolio: &Portfolio) -> PortfolioOutcome {
+    let start_time = Instant::now();
+    let strategies = &portfolio.strategies;
+    assert!(!strategies.is_empty(), "Portfolio without strategies");
+    let tokens: Vec<CancelToken> = strategies
+        .iter()
+        .map(|(_, config)| {
+            CancelToken::linked(portfolio.cancel.iter().chain(&config.cancel).cloned())
+        })
+        .collect();
+    let mut outcomes: Vec<Option<SolveOutcome>> = vec![None; strategies.len()];
+    let mut best: Option<usize> = None;
+
+    thread::scope(|scope| {
+        let (sender, results) = mpsc::channel();
+        for (index, ((_, config), token)) in strategies.iter().zip(&tokens).enumerate() {
+            let config = config.clone().cancel_token(token.clone());
+            let sender = sender.clone();
+            scope.spawn(move || {
+                // The receiver only hangs up once every strategy was heard from.
+                let _ = sender.send((index, solve_with(level, &config)));
+            });
+        }
+        drop(sender);
+
+        let cancel_all = || tokens.iter().for_each(CancelToken::cancel);
+        loop {
+            let received = match portfolio.deadline {
+                Some(deadline) => {
+                    let left = deadline.saturating_sub(start_time.elapsed());
+                    results.recv_timeout(left).ok()
+                }
+                None => results.recv().ok(),
+            };
+            let Some((index, outcome)) = received else {
+                // Out of time, or every strategy has answered.
+                cancel_all();
+                break;
+            };
+
+            if let Some(solution) = outcome.solution() {
+                let shorter = best.is_none_or(|best| {
+                    let current = outcomes[best].as_ref().and_then(SolveOutcome::solution);
+                    current.is_none_or(|current| {
+                        (solution.pushes, solution.moves) < (current.pushes, current.moves)
+                    })
+                });
+                if shorter {
+                    best = Some(index);
+                }
+                if portfolio.deadline.is_none() {
+                    outcomes[index] = Some(outcome);
+                    cancel_all();
+                    break;
+                }
+            }
+            // Only a search that ran out of states proves it, no other strategy can do better.
+            if let SolveOutcome::Unsolvable { .. } = outcome {
+                outcomes[index] = Some(outcome);
+                cancel_all();
+                break;
+            }
+            outcomes[index] = Some(outcome);
+        }
+
+        // Collect what the cancelled strategies stopped with.
+        for (index, outcome) in results {
+            outcomes[index] = Some(outcome);
+        }
+    });
+
+    if let Some(index) = best {
+        return PortfolioOutcome {
+            winner: Some(strategies[index].0.clone()),
+            outcome: outcomes[index].take().unwrap(),
+        };
+    }
+    let mut outcomes: Vec<SolveOutcome> = outcomes.into_iter().flatten().collect();
+    let unsolvable = outcomes
+        .iter()
+        .position(|outcome| matches!(outcome, SolveOutcome::Unsolvable { .. }));
+    PortfolioOutcome {
+        winner: None,
+        outcome: outcomes.swap_remove(unsolvable.unwrap_or(0)),
+    }
+}
@@ -0,0 +1,43 @@
+use crate::distance::{compute_pull_distance_map, side_blocks};
+use crate::{BoolGrid, DistanceMap, Level, Node, Pos, ROOT};
+
+// What the reverse search needs on top of the level: the pull distances towards every initial
+// box square and the squares from which no box can be pulled back to any of them.
+pub(crate) struct PullModel {
+    pub(crate) start_maps: Vec<DistanceMap>, // Per initial box, same order as `Level::boxes`
+    pub(crate) dead: BoolGrid,
+}
+
+impl PullModel {
+    pub(crate) fn new(level: &Level) -> PullModel {
+        let grid = &level.grid;
+        let blocks = side_blocks(grid);
+        let start_maps: Vec<DistanceMap> = level
+            .boxes
+            .iter()
+            .map(|&start| compute_pull_distance_map(start, grid, &blocks))
+            .collect();
+
+        let mut dead = grid.map(false);
+        for pos in 0..grid.len() as Pos {
+            dead[pos] = start_maps.iter().all(|map| map[pos] == u16::MAX);
+        }
+        PullModel { start_maps, dead }
+    }
+}
+
+// The pulls from `last` back to the solved layout are the forward pushes in playing order.
+// Returns them as a chain `rebuild_path` can replay, the root first.
+pub(crate) fn forward_pushes(nodes: &[Node], last: u32) -> Vec<Node> {
+    let mut pushes = vec![nodes[ROOT as usize]];
+    let mut current = last;
+    while current != ROOT {
+        let node = nodes[current as usize];
+        pushes.push(Node {
+            parent: pushes.len() as u32 - 1,
+            ..node
+        });
+        current = node.parent;
+    }
+    pushes
+}
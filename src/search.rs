@@ -0,0 +1,516 @@
+use std::collections::BinaryHeap;
+use std::mem::size_of;
+use std::time::Instant;
+
+use crate::expand::{BoxMove, Expander, Moves};
+use crate::reverse::{PullModel, forward_pushes};
+use crate::visited::{NO_NODE, Visit, Visited};
+use crate::zobrist::Zobrist;
+use crate::{
+    Algorithm, BoolGrid, BoxOrGoal, DistanceMap, Heuristic, LIMIT_CHECK_INTERVAL, Level, Limit,
+    Node, Pos, Progress, ROOT, SearchObserver, SearchStats, Solution, SolveOutcome, SolverConfig,
+    State, get_normalized_player, is_free, rebuild_path,
+};
+
+// One best-first search over box layouts: the open list, the states seen so far and the arena
+// their pushes live in, plus the scratch buffers of an expansion.
+pub(crate) struct Frontier<'a> {
+    level: &'a Level,
+    heuristic: Heuristic,
+    // An admissible heuristic only yields optimal solutions if a state can be reopened
+    // when a cheaper path to it shows up, so keep the best cost per state in that case.
+    reopen: bool,
+    greedy: bool, // Order by the estimate alone, ignoring the pushes made so far
+    zobrist: Zobrist,
+    pub(crate) queue: BinaryHeap<State>,
+    pub(crate) visited: Visited,
+    pub(crate) nodes: Vec<Node>,
+    max_open: Option<usize>,
+    pub(crate) pruned: bool, // Some open states were dropped, running dry proves nothing
+    expander: Expander<'a>,
+    children: Vec<(BoxOrGoal, BoxMove)>, // Those of the state being expanded
+    norm_buffer: BoolGrid,
+    stack: Vec<Pos>,
+}
+
+impl<'a> Frontier<'a> {
+    // Pushes from the initial layout towards the goals.
+    pub(crate) fn forward(level: &'a Level, config: &SolverConfig) -> Frontier<'a> {
+        let mut frontier = Frontier::empty(level, config, Moves::Push);
+        let boxes = level.boxes.clone();
+        let hash = frontier.zobrist.boxes(&boxes);
+        let norm_player = frontier.normalize(level.player, &boxes);
+        if let Visit::New(entry) = frontier.visited.visit(
+            frontier.zobrist.state(hash, norm_player),
+            &boxes,
+            norm_player,
+            0,
+            frontier.reopen,
+        ) {
+            frontier.visited.set_node(entry, ROOT);
+        }
+
+        let priority = frontier.heuristic.estimate(&boxes, &level.goal_maps);
+        frontier.queue.push(State {
+            boxes,
+            player: level.player,
+            hash,
+            node: ROOT,
+            cost: 0,
+            priority,
+        });
+        frontier
+    }
+
+    // Pushes like `forward`, but starts out empty. For the threads of a parallel search that
+    // do not own the initial state.
+    pub(crate) fn partition(level: &'a Level, config: &SolverConfig) -> Frontier<'a> {
+        Frontier::empty(level, config, Moves::Push)
+    }
+
+    // Pulls from the solved layout, with the player in each region the boxes leave open,
+    // towards the initial layout.
+    pub(crate) fn backward(level: &'a Level, config: &SolverConfig) -> Frontier<'a> {
+        let grid = &level.grid;
+        let target_player = get_normalized_player(
+            level.player,
+            &level.boxes,
+            grid,
+            &mut grid.map(false),
+            &mut Vec::new(),
+        );
+        let moves = Moves::Pull {
+            model: PullModel::new(level),
+            target_player,
+        };
+        let mut frontier = Frontier::empty(level, config, moves);
+
+        let mut solved = level.goals.clone();
+        solved.sort_unstable();
+        let hash = frontier.zobrist.boxes(&solved);
+        let priority = frontier.heuristic.estimate(&solved, frontier.maps());
+        if priority == u16::MAX {
+            return frontier;
+        }
+
+        for player in 0..grid.len() as Pos {
+            if !is_free(player, &solved, grid) {
+                continue;
+            }
+            let norm_player = frontier.normalize(player, &solved);
+            let state_hash = frontier.zobrist.state(hash, norm_player);
+            if let Visit::New(entry) =
+                frontier
+                    .visited
+                    .visit(state_hash, &solved, norm_player, 0, frontier.reopen)
+            {
+                frontier.visited.set_node(entry, ROOT);
+                frontier.queue.push(State {
+                    boxes: solved.clone(),
+                    player: norm_player,
+                    hash,
+                    node: ROOT,
+                    cost: 0,
+                    priority,
+                });
+            }
+        }
+        frontier
+    }
+
+    fn empty(level: &'a Level, config: &SolverConfig, moves: Moves) -> Frontier<'a> {
+        let grid = &level.grid;
+        let box_count = level.boxes.len();
+        let greedy = config.algorithm == Algorithm::GreedyBestFirst;
+        Frontier {
+            level,
+            heuristic: config.heuristic,
+            reopen: config.heuristic.is_admissible() && !greedy,
+            greedy,
+            zobrist: Zobrist::new(grid),
+            queue: BinaryHeap::new(),
+            visited: Visited::new(grid, box_count, config.table_bytes),
+            nodes: vec![Node {
+                parent: ROOT,
+                box_from: 0,
+                dir: 0,
+            }],
+            max_open: config
+                .open_bytes
+                .map(|bytes| (bytes / open_state_bytes(box_count)).max(2)),
+            pruned: false,
+            expander: Expander::new(level, config, moves),
+            children: Vec::new(),
+            norm_buffer: grid.map(false),
+            stack: Vec::with_capacity(grid.len()),
+        }
+    }
+
+    // The distance maps the heuristic matches boxes against.
+    fn maps(&self) -> &[DistanceMap] {
+        match &self.expander.moves {
+            Moves::Push => &self.level.goal_maps,
+            Moves::Pull { model, .. } => &model.start_maps,
+        }
+    }
+
+    fn normalize(&mut self, player: Pos, boxes: &BoxOrGoal) -> Pos {
+        get_normalized_player(
+            player,
+            boxes,
+            &self.level.grid,
+            &mut self.norm_buffer,
+            &mut self.stack,
+        )
+    }
+
+    pub(crate) fn is_target(&mut self, state: &State) -> bool {
+        match self.expander.moves {
+            Moves::Push => state.boxes.iter().all(|b| self.level.goals.contains(b)),
+            Moves::Pull { target_player, .. } => {
+                state.boxes == self.level.boxes
+                    && self.normalize(state.player, &state.boxes) == target_player
+            }
+        }
+    }
+
+    // The solution ending in `state`, which must be a target.
+    pub(crate) fn solution(&self, state: &State, stats: SearchStats) -> Solution {
+        match self.expander.moves {
+            Moves::Push => solution_from(self.level, &self.nodes, state.node, stats),
+            Moves::Pull { .. } => {
+                let pushes = forward_pushes(&self.nodes, state.node);
+                solution_from(self.level, &pushes, pushes.len() as u32 - 1, stats)
+            }
+        }
+    }
+
+    pub(crate) fn expand(&mut self, state: &State, stats: &mut SearchStats) {
+        let cost = state.cost + 1;
+        self.for_each_child(
+            state,
+            stats,
+            |frontier, boxes, hash, player, push, stats| {
+                frontier.add_child(boxes, hash, player, cost, push, stats);
+            },
+        );
+    }
+
+    // Hands every child of `state` that survives the deadlock checks to `visit`, along with
+    // the Zobrist hash of its boxes, the player position and its push.
+    pub(crate) fn for_each_child<F>(&mut self, state: &State, stats: &mut SearchStats, mut visit: F)
+    where
+        F: FnMut(&mut Self, BoxOrGoal, u64, Pos, Node, &mut SearchStats),
+    {
+        let mut children = std::mem::take(&mut self.children);
+        self.expander
+            .for_each_child(&state.boxes, state.player, stats, |boxes, step, _| {
+                children.push((boxes, step));
+            });
+
+        for (boxes, step) in children.drain(..) {
+            let hash = self.zobrist.push(state.hash, step.from, step.to);
+            // Pulls are stored as the forward push that undoes them.
+            let box_from = match self.expander.moves {
+                Moves::Push => step.from,
+                Moves::Pull { .. } => step.to,
+            };
+            let push = Node {
+                parent: state.node,
+                box_from,
+                dir: step.dir,
+            };
+            visit(self, boxes, hash, step.player, push, stats);
+        }
+        self.children = children;
+    }
+
+    // Queues the state unless it is a duplicate or the heuristic rules it out, returns whether
+    // it was queued.
+    pub(crate) fn add_child(
+        &mut self,
+        boxes: BoxOrGoal,
+        hash: u64,
+        player: Pos,
+        cost: u16,
+        push: Node,
+        stats: &mut SearchStats,
+    ) -> bool {
+        let norm_player = self.normalize(player, &boxes);
+        let state_hash = self.zobrist.state(hash, norm_player);
+
+        let entry = match self
+            .visited
+            .visit(state_hash, &boxes, norm_player, cost, self.reopen)
+        {
+            Visit::New(entry) | Visit::Cheaper(entry) => entry,
+            Visit::Known => {
+                stats.duplicates_pruned += 1;
+                return false;
+            }
+        };
+
+        let h = self.heuristic.estimate(&boxes, self.maps());
+        if h == u16::MAX {
+            stats.deadlocks.unmatched += 1;
+            return false;
+        }
+
+        self.nodes.push(push);
+        self.visited.set_node(entry, (self.nodes.len() - 1) as u32);
+        self.queue.push(State {
+            boxes,
+            player,
+            hash,
+            node: (self.nodes.len() - 1) as u32,
+            cost,
+            priority: if self.greedy {
+                h
+            } else {
+                cost.saturating_add(h)
+            },
+        });
+        stats.nodes_generated += 1;
+        stats.peak_open = stats.peak_open.max(self.queue.len());
+        true
+    }
+
+    // SMA*-style fallback for a full open list: keeps the better half, forgets the rest in the
+    // visited table so another path can bring them back, then drops the arena nodes no open
+    // state descends from.
+    pub(crate) fn prune_if_full(&mut self, stats: &mut SearchStats) {
+        if self.max_open.is_none_or(|max| self.queue.len() < max) {
+            return;
+        }
+        self.pruned = true;
+
+        // `State` orders better states as greater, so the best come first.
+        let mut states = std::mem::take(&mut self.queue).into_vec();
+        states.sort_unstable_by(|a, b| b.cmp(a));
+        let dropped = states.split_off(states.len() / 2);
+        stats.open_pruned += dropped.len();
+
+        for state in &dropped {
+            let norm_player = self.normalize(state.player, &state.boxes);
+            let state_hash = self.zobrist.state(state.hash, norm_player);
+            self.visited.forget(state_hash, &state.boxes, norm_player);
+        }
+
+        // Parents always come before their children, so one forward pass can renumber them.
+        let nodes = &mut self.nodes;
+        let mut keep = vec![false; nodes.len()];
+        keep[ROOT as usize] = true;
+        for state in &states {
+            let mut node = state.node;
+            while !keep[node as usize] {
+                keep[node as usize] = true;
+                node = nodes[node as usize].parent;
+            }
+        }
+
+        let mut renumbered = vec![NO_NODE; nodes.len()];
+        let mut next = 0;
+        for index in 0..nodes.len() {
+            if keep[index] {
+                let node = nodes[index];
+                renumbered[index] = next as u32;
+                nodes[next] = Node {
+                    parent: renumbered[node.parent as usize],
+                    ..node
+                };
+                next += 1;
+            }
+        }
+        nodes.truncate(next);
+
+        for state in &mut states {
+            state.node = renumbered[state.node as usize];
+        }
+        self.visited.renumber_nodes(&renumbered);
+        self.queue = BinaryHeap::from(states);
+    }
+
+    // The arena node of `state` in the other frontier, if that one has reached it too.
+    fn meets(&mut self, other: &mut Frontier, state: &State) -> Option<u32> {
+        let norm_player = self.normalize(state.player, &state.boxes);
+        let state_hash = self.zobrist.state(state.hash, norm_player);
+        other.visited.node_of(state_hash, &state.boxes, norm_player)
+    }
+
+    // Allocated table slots plus the boxes that no longer fit inline. Every state has the same
+    // number of boxes, so this stays O(1) instead of walking the queue.
+    pub(crate) fn memory_bytes(&self) -> usize {
+        let box_count = self.level.boxes.len();
+        let spilled_boxes = open_state_bytes(box_count) - size_of::<State>() - size_of::<Node>();
+
+        self.visited.memory_bytes()
+            + self.queue.capacity() * size_of::<State>()
+            + self.nodes.capacity() * size_of::<Node>()
+            + self.queue.len() * spilled_boxes
+    }
+}
+
+// What one open state holds on to: its queue entry, boxes that spill out of the `SmallVec`
+// and its node in the arena.
+fn open_state_bytes(box_count: usize) -> usize {
+    let spilled_boxes = if box_count > BoxOrGoal::new().inline_size() {
+        box_count * size_of::<Pos>()
+    } else {
+        0
+    };
+    size_of::<State>() + spilled_boxes + size_of::<Node>()
+}
+
+// Refreshes the numbers that are too costly to keep current on every expansion.
+fn update_stats(frontiers: &[Frontier], stats: &mut SearchStats, start_time: Instant) {
+    stats.elapsed = start_time.elapsed();
+    stats.memory_bytes = frontiers.iter().map(Frontier::memory_bytes).sum();
+    let visited = frontiers.iter().map(|f| f.visited.len()).sum();
+    stats.peak_visited = stats.peak_visited.max(visited);
+    stats.evictions = frontiers.iter().map(|f| f.visited.evictions()).sum();
+}
+
+// The pushes up to `forward_last` followed by the ones the backward frontier pulled back from
+// the solved layout to `backward_last`, which is the same state. Returned as a chain for
+// `rebuild_path`, the root first.
+fn joined_pushes(
+    forward: &[Node],
+    forward_last: u32,
+    backward: &[Node],
+    backward_last: u32,
+) -> Vec<Node> {
+    let mut pushes = Vec::new();
+    let mut current = forward_last;
+    while current != ROOT {
+        pushes.push(forward[current as usize]);
+        current = forward[current as usize].parent;
+    }
+    pushes.push(forward[ROOT as usize]);
+    pushes.reverse();
+    pushes.extend(forward_pushes(backward, backward_last).into_iter().skip(1));
+
+    for (index, push) in pushes.iter_mut().enumerate().skip(1) {
+        push.parent = index as u32 - 1;
+    }
+    pushes
+}
+
+// Builds the `Solution` whose last push is `nodes[last]`.
+pub(crate) fn solution_from(
+    level: &Level,
+    nodes: &[Node],
+    last: u32,
+    stats: SearchStats,
+) -> Solution {
+    let grid = &level.grid;
+    let path = rebuild_path(nodes, last, level.player, &level.boxes, grid);
+    // Every goal holds a box now.
+    let mut boxes = level.goals.clone();
+    boxes.sort_unstable();
+
+    Solution {
+        pushes: path.bytes().filter(u8::is_ascii_uppercase).count(),
+        moves: path.len(),
+        boxes: boxes.iter().map(|&pos| grid.coords(pos)).collect(),
+        path,
+        stats,
+    }
+}
+
+// Runs the frontiers until one reaches its target or, with a forward and a backward frontier,
+// until one of them pops a state the other has already reached.
+pub(crate) fn best_first<O: SearchObserver + ?Sized>(
+    mut frontiers: Vec<Frontier>,
+    config: &SolverConfig,
+    observer: &mut O,
+    start_time: Instant,
+) -> SolveOutcome {
+    let mut stats = SearchStats::default();
+    // The highest priority popped so far per frontier, the smallest is reported as the bound.
+    let mut bounds = vec![0; frontiers.len()];
+    let mut best_bound = None;
+
+    loop {
+        // Expand from the smaller open list, so neither side runs away with the memory.
+        let index = (0..frontiers.len())
+            .min_by_key(|&i| frontiers[i].queue.len())
+            .unwrap();
+        let Some(state) = frontiers[index].queue.pop() else {
+            break;
+        };
+
+        stats.nodes_expanded += 1;
+        observer.on_expand(state.cost as usize, state.priority as usize);
+        bounds[index] = bounds[index].max(state.priority);
+        let bound = bounds.iter().copied().min().unwrap();
+        if best_bound.is_none_or(|best| bound > best) {
+            best_bound = Some(bound);
+            observer.on_new_best_lower_bound(bound as usize);
+        }
+
+        if frontiers[index].is_target(&state) {
+            update_stats(&frontiers, &mut stats, start_time);
+            return SolveOutcome::Solved(frontiers[index].solution(&state, stats));
+        }
+        if let [forward, backward] = frontiers.as_mut_slice() {
+            let pushes = if index == 0 {
+                forward
+                    .meets(backward, &state)
+                    .map(|node| joined_pushes(&forward.nodes, state.node, &backward.nodes, node))
+            } else {
+                backward
+                    .meets(forward, &state)
+                    .map(|node| joined_pushes(&forward.nodes, node, &backward.nodes, state.node))
+            };
+            if let Some(pushes) = pushes {
+                update_stats(&frontiers, &mut stats, start_time);
+                let last = pushes.len() as u32 - 1;
+                return SolveOutcome::Solved(solution_from(
+                    frontiers[0].level,
+                    &pushes,
+                    last,
+                    stats,
+                ));
+            }
+        }
+
+        if stats.nodes_expanded.is_multiple_of(LIMIT_CHECK_INTERVAL) {
+            update_stats(&frontiers, &mut stats, start_time);
+        }
+        if stats
+            .nodes_expanded
+            .is_multiple_of(config.progress_interval)
+        {
+            update_stats(&frontiers, &mut stats, start_time);
+            observer.on_progress(&Progress {
+                stats: &stats,
+                open_len: frontiers.iter().map(|f| f.queue.len()).sum(),
+                visited_len: frontiers.iter().map(|f| f.visited.len()).sum(),
+                f_bound: state.priority as usize,
+            });
+        }
+        if config.is_cancelled() {
+            update_stats(&frontiers, &mut stats, start_time);
+            return SolveOutcome::Cancelled { stats };
+        }
+        if let Some(limit) = config.exceeded(&stats) {
+            update_stats(&frontiers, &mut stats, start_time);
+            return SolveOutcome::LimitReached { limit, stats };
+        }
+
+        let frontier = &mut frontiers[index];
+        frontier.expand(&state, &mut stats);
+        frontier.prune_if_full(&mut stats);
+    }
+
+    update_stats(&frontiers, &mut stats, start_time);
+    // One side ran dry, every state it could reach was expanded without meeting the other.
+    if frontiers.iter().any(|f| f.pruned && f.queue.is_empty()) {
+        // The dropped states were never expanded, so this proves nothing about the level.
+        return SolveOutcome::LimitReached {
+            limit: Limit::Memory,
+            stats,
+        };
+    }
+    SolveOutcome::Unsolvable { stats }
+}
@@ -0,0 +1,420 @@
+use std::mem::size_of;
+
+use crate::grid::{CellMap, Grid};
+use crate::{BoxOrGoal, Pos};
+
+// How the box layout of a state is packed into `u64` words. Both are canonical because the
+// boxes are kept sorted, the smaller one is picked once per level.
+#[derive(Clone, Copy)]
+enum KeyLayout {
+    Bitset, // One bit per floor cell, good for crowded levels
+    Packed, // Two cell indices per word, good for big levels with few boxes
+}
+
+struct KeyCodec {
+    layout: KeyLayout,
+    words: usize,              // Words per key
+    floor_index: CellMap<u32>, // Bit of each floor cell in the `Bitset` layout
+}
+
+impl KeyCodec {
+    fn new(grid: &Grid, box_count: usize) -> KeyCodec {
+        let mut floor_index = grid.map(0);
+        let mut floor_count = 0;
+        for pos in 0..grid.len() as Pos {
+            if !grid.is_wall(pos) {
+                floor_index[pos] = floor_count;
+                floor_count += 1;
+            }
+        }
+
+        let bitset_words = (floor_count as usize).div_ceil(64);
+        let packed_words = box_count.div_ceil(2);
+        let (layout, words) = if bitset_words <= packed_words {
+            (KeyLayout::Bitset, bitset_words)
+        } else {
+            (KeyLayout::Packed, packed_words)
+        };
+
+        KeyCodec {
+            layout,
+            words,
+            floor_index,
+        }
+    }
+
+    fn encode(&self, boxes: &BoxOrGoal, key: &mut [u64]) {
+        key.fill(0);
+        match self.layout {
+            KeyLayout::Bitset => {
+                for &pos in boxes {
+                    let bit = self.floor_index[pos] as usize;
+                    key[bit / 64] |= 1 << (bit % 64);
+                }
+            }
+            KeyLayout::Packed => {
+                for (i, &pos) in boxes.iter().enumerate() {
+                    key[i / 2] |= (pos as u64) << (32 * (i % 2));
+                }
+            }
+        }
+    }
+}
+
+// `New` and `Cheaper` carry the entry of the state, to attach its node with `set_node`.
+pub(crate) enum Visit {
+    New(usize),
+    Cheaper(usize), // Seen before at a higher cost, only reported when reopening is allowed
+    Known,
+}
+
+// Node of an entry whose state never made it into the open list, or whose node was dropped.
+pub(crate) const NO_NODE: u32 = u32::MAX;
+
+// Cost of a forgotten entry: the state was dropped from the open list, so the next path to
+// it counts as new again.
+const FORGOTTEN: u16 = u16::MAX;
+
+// The states the search has seen with the lowest cost each was reached at. Unbounded unless
+// `SolverConfig::transposition_table_mb` asks for a fixed-size table.
+pub(crate) enum Visited {
+    Exact(VisitedSet),
+    Bounded(TranspositionTable),
+}
+
+impl Visited {
+    pub(crate) fn new(grid: &Grid, box_count: usize, table_bytes: Option<usize>) -> Visited {
+        let codec = KeyCodec::new(grid, box_count);
+        match table_bytes {
+            Some(bytes) => Visited::Bounded(TranspositionTable::new(codec, bytes)),
+            None => Visited::Exact(VisitedSet::new(codec)),
+        }
+    }
+
+    // Records a state reached at `cost`. With `reopen` a known state reached more cheaply
+    // takes the new cost and counts as `Cheaper`, otherwise it is `Known`.
+    pub(crate) fn visit(
+        &mut self,
+        hash: u64,
+        boxes: &BoxOrGoal,
+        player: Pos,
+        cost: u16,
+        reopen: bool,
+    ) -> Visit {
+        match self {
+            Visited::Exact(set) => set.visit(hash, boxes, player, cost, reopen),
+            Visited::Bounded(table) => table.visit(hash, boxes, player, cost, reopen),
+        }
+    }
+
+    // Whether the state was already reached at no more than `cost`. Unlike `visit` it leaves
+    // the set as it is.
+    pub(crate) fn is_known(
+        &mut self,
+        hash: u64,
+        boxes: &BoxOrGoal,
+        player: Pos,
+        cost: u16,
+    ) -> bool {
+        let (found, costs) = match self {
+            Visited::Exact(set) => (set.find(hash, boxes, player), &set.costs),
+            Visited::Bounded(table) => (table.find(hash, boxes, player), &table.costs),
+        };
+        found.is_ok_and(|entry| costs[entry] <= cost)
+    }
+
+    // Lets the next path to this state through, used for states dropped from the open list.
+    pub(crate) fn forget(&mut self, hash: u64, boxes: &BoxOrGoal, player: Pos) {
+        match self {
+            Visited::Exact(set) => set.forget(hash, boxes, player),
+            Visited::Bounded(table) => table.forget(hash, boxes, player),
+        }
+    }
+
+    pub(crate) fn set_node(&mut self, entry: usize, node: u32) {
+        match self {
+            Visited::Exact(set) => set.nodes[entry] = node,
+            Visited::Bounded(table) => table.nodes[entry] = node,
+        }
+    }
+
+    // The arena node of a state this search has queued, if it still knows it.
+    pub(crate) fn node_of(&mut self, hash: u64, boxes: &BoxOrGoal, player: Pos) -> Option<u32> {
+        let (found, costs, nodes) = match self {
+            Visited::Exact(set) => (set.find(hash, boxes, player), &set.costs, &set.nodes),
+            Visited::Bounded(table) => {
+                (table.find(hash, boxes, player), &table.costs, &table.nodes)
+            }
+        };
+        let entry = found.ok()?;
+        (costs[entry] != FORGOTTEN && nodes[entry] != NO_NODE).then_some(nodes[entry])
+    }
+
+    // Follows a compaction of the node arena, `renumbered` maps old nodes to new ones.
+    pub(crate) fn renumber_nodes(&mut self, renumbered: &[u32]) {
+        let nodes = match self {
+            Visited::Exact(set) => &mut set.nodes,
+            Visited::Bounded(table) => &mut table.nodes,
+        };
+        for node in nodes.iter_mut().filter(|node| **node != NO_NODE) {
+            *node = renumbered[*node as usize];
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Visited::Exact(set) => set.costs.len(),
+            Visited::Bounded(table) => table.len,
+        }
+    }
+
+    // Entries overwritten to make room, always 0 for the unbounded set.
+    pub(crate) fn evictions(&self) -> usize {
+        match self {
+            Visited::Exact(_) => 0,
+            Visited::Bounded(table) => table.evictions,
+        }
+    }
+
+    pub(crate) fn memory_bytes(&self) -> usize {
+        match self {
+            Visited::Exact(set) => set.memory_bytes(),
+            Visited::Bounded(table) => table.memory_bytes(),
+        }
+    }
+}
+
+// Keys are stored back to back in one buffer and found through an open-addressing index on
+// their Zobrist hash, so a state costs a few words instead of a heap-backed `SmallVec`.
+pub(crate) struct VisitedSet {
+    codec: KeyCodec,
+    keys: Vec<u64>,    // `codec.words` per entry
+    players: Vec<Pos>, // Normalized player of each entry
+    hashes: Vec<u64>,  // Zobrist hash of each entry, kept to grow the index
+    costs: Vec<u16>,   // Cheapest known cost of each entry
+    nodes: Vec<u32>,   // Arena node of each entry, or `NO_NODE`
+    slots: Vec<u32>,   // Entry index + 1, or 0 when free. Length is a power of two.
+    scratch: Vec<u64>, // The key being looked up
+}
+
+const INITIAL_SLOTS: usize = 1 << 16;
+
+impl VisitedSet {
+    fn new(codec: KeyCodec) -> VisitedSet {
+        VisitedSet {
+            scratch: vec![0; codec.words],
+            codec,
+            keys: Vec::new(),
+            players: Vec::new(),
+            hashes: Vec::new(),
+            costs: Vec::new(),
+            nodes: Vec::new(),
+            slots: vec![0; INITIAL_SLOTS],
+        }
+    }
+
+    fn visit(
+        &mut self,
+        hash: u64,
+        boxes: &BoxOrGoal,
+        player: Pos,
+        cost: u16,
+        reopen: bool,
+    ) -> Visit {
+        // Keep the load factor below 7/8 so probe chains stay short.
+        if (self.costs.len() + 1) * 8 > self.slots.len() * 7 {
+            self.grow();
+        }
+
+        let slot = match self.find(hash, boxes, player) {
+            Ok(entry) => return update_cost(&mut self.costs[entry], entry, cost, reopen),
+            Err(slot) => slot,
+        };
+
+        let entry = self.costs.len();
+        self.slots[slot] = entry as u32 + 1;
+        self.keys.extend_from_slice(&self.scratch);
+        self.players.push(player);
+        self.hashes.push(hash);
+        self.costs.push(cost);
+        self.nodes.push(NO_NODE);
+        Visit::New(entry)
+    }
+
+    fn forget(&mut self, hash: u64, boxes: &BoxOrGoal, player: Pos) {
+        if let Ok(entry) = self.find(hash, boxes, player) {
+            self.costs[entry] = FORGOTTEN;
+        }
+    }
+
+    // The entry holding this state, or the free slot where it belongs.
+    fn find(&mut self, hash: u64, boxes: &BoxOrGoal, player: Pos) -> Result<usize, usize> {
+        self.codec.encode(boxes, &mut self.scratch);
+        let words = self.codec.words;
+        let mask = self.slots.len() - 1;
+        let mut slot = hash as usize & mask;
+
+        loop {
+            let entry = match self.slots[slot] {
+                0 => return Err(slot),
+                stored => stored as usize - 1,
+            };
+            if self.hashes[entry] == hash
+                && self.players[entry] == player
+                && self.keys[entry * words..(entry + 1) * words] == self.scratch[..]
+            {
+                return Ok(entry);
+            }
+            slot = (slot + 1) & mask;
+        }
+    }
+
+    fn memory_bytes(&self) -> usize {
+        self.keys.capacity() * size_of::<u64>()
+            + self.players.capacity() * size_of::<Pos>()
+            + self.hashes.capacity() * size_of::<u64>()
+            + self.costs.capacity() * size_of::<u16>()
+            + self.nodes.capacity() * size_of::<u32>()
+            + self.slots.capacity() * size_of::<u32>()
+    }
+
+    fn grow(&mut self) {
+        self.slots = vec![0; self.slots.len() * 2];
+        let mask = self.slots.len() - 1;
+
+        for (entry, &hash) in self.hashes.iter().enumerate() {
+            let mut slot = hash as usize & mask;
+            while self.slots[slot] != 0 {
+                slot = (slot + 1) & mask;
+            }
+            self.slots[slot] = entry as u32 + 1;
+        }
+    }
+}
+
+// Entries per bucket of the transposition table. A state can only live in its own bucket.
+const BUCKET: usize = 4;
+
+// Marks a free entry, no cell index gets this large.
+const EMPTY: Pos = Pos::MAX;
+
+// A fixed number of entries allocated up front. When the bucket of a new state is full, the
+// entry with the highest cost is overwritten: deep states cut off the smallest subtrees, so
+// losing them costs the least re-expansion.
+pub(crate) struct TranspositionTable {
+    codec: KeyCodec,
+    buckets: usize,
+    keys: Vec<u64>,
+    players: Vec<Pos>,
+    hashes: Vec<u64>,
+    costs: Vec<u16>,
+    nodes: Vec<u32>,
+    scratch: Vec<u64>,
+    len: usize,
+    evictions: usize,
+}
+
+impl TranspositionTable {
+    fn new(codec: KeyCodec, bytes: usize) -> TranspositionTable {
+        let entry_bytes = codec.words * size_of::<u64>()
+            + size_of::<Pos>()
+            + size_of::<u64>()
+            + size_of::<u16>()
+            + size_of::<u32>();
+        let buckets = (bytes / (entry_bytes * BUCKET)).max(1);
+        let entries = buckets * BUCKET;
+
+        TranspositionTable {
+            buckets,
+            keys: vec![0; entries * codec.words],
+            players: vec![EMPTY; entries],
+            hashes: vec![0; entries],
+            costs: vec![0; entries],
+            nodes: vec![NO_NODE; entries],
+            scratch: vec![0; codec.words],
+            codec,
+            len: 0,
+            evictions: 0,
+        }
+    }
+
+    fn visit(
+        &mut self,
+        hash: u64,
+        boxes: &BoxOrGoal,
+        player: Pos,
+        cost: u16,
+        reopen: bool,
+    ) -> Visit {
+        let entry = match self.find(hash, boxes, player) {
+            Ok(entry) => return update_cost(&mut self.costs[entry], entry, cost, reopen),
+            Err(entry) => entry,
+        };
+
+        if self.players[entry] == EMPTY {
+            self.len += 1;
+        } else {
+            self.evictions += 1;
+        }
+        let words = self.codec.words;
+        self.keys[entry * words..(entry + 1) * words].copy_from_slice(&self.scratch);
+        self.players[entry] = player;
+        self.hashes[entry] = hash;
+        self.costs[entry] = cost;
+        self.nodes[entry] = NO_NODE;
+        Visit::New(entry)
+    }
+
+    fn forget(&mut self, hash: u64, boxes: &BoxOrGoal, player: Pos) {
+        if let Ok(entry) = self.find(hash, boxes, player) {
+            self.costs[entry] = FORGOTTEN;
+        }
+    }
+
+    // The entry holding this state, or the one to overwrite with it: a free entry if the
+    // bucket has one, else the most expensive.
+    fn find(&mut self, hash: u64, boxes: &BoxOrGoal, player: Pos) -> Result<usize, usize> {
+        self.codec.encode(boxes, &mut self.scratch);
+        let words = self.codec.words;
+        let first = (hash % self.buckets as u64) as usize * BUCKET;
+        let mut victim = first;
+
+        for entry in first..first + BUCKET {
+            if self.players[entry] == EMPTY {
+                victim = entry;
+                break;
+            }
+            if self.hashes[entry] == hash
+                && self.players[entry] == player
+                && self.keys[entry * words..(entry + 1) * words] == self.scratch[..]
+            {
+                return Ok(entry);
+            }
+            // Forgotten entries have the highest cost, so they go first.
+            if self.costs[entry] > self.costs[victim] {
+                victim = entry;
+            }
+        }
+        Err(victim)
+    }
+
+    fn memory_bytes(&self) -> usize {
+        self.keys.capacity() * size_of::<u64>()
+            + self.players.capacity() * size_of::<Pos>()
+            + self.hashes.capacity() * size_of::<u64>()
+            + self.costs.capacity() * size_of::<u16>()
+            + self.nodes.capacity() * size_of::<u32>()
+    }
+}
+
+fn update_cost(best: &mut u16, entry: usize, cost: u16, reopen: bool) -> Visit {
+    if *best == FORGOTTEN {
+        *best = cost;
+        Visit::New(entry)
+    } else if reopen && cost < *best {
+        *best = cost;
+        Visit::Cheaper(entry)
+    } else {
+        Visit::Known
+    }
+}
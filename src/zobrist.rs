@@ -0,0 +1,47 @@
+use crate::grid::{CellMap, Grid};
+use crate::{BoxOrGoal, Pos};
+
+// Random 64-bit keys per cell. A box layout hashes to the XOR of the keys of its boxes, so a
+// push updates the hash with two XORs instead of rehashing every box.
+pub(crate) struct Zobrist {
+    boxes: CellMap<u64>,
+    players: CellMap<u64>,
+}
+
+impl Zobrist {
+    pub(crate) fn new(grid: &Grid) -> Zobrist {
+        // A fixed seed gives the same keys on every run, so searches stay reproducible.
+        let mut seed = 0x9E37_79B9_7F4A_7C15;
+        let mut boxes = grid.map(0);
+        let mut players = grid.map(0);
+
+        for pos in 0..grid.len() as Pos {
+            boxes[pos] = splitmix64(&mut seed);
+            players[pos] = splitmix64(&mut seed);
+        }
+
+        Zobrist { boxes, players }
+    }
+
+    pub(crate) fn boxes(&self, boxes: &BoxOrGoal) -> u64 {
+        boxes.iter().fold(0, |hash, &pos| hash ^ self.boxes[pos])
+    }
+
+    // Hash of the layout after moving one box from `from` to `to`.
+    pub(crate) fn push(&self, boxes_hash: u64, from: Pos, to: Pos) -> u64 {
+        boxes_hash ^ self.boxes[from] ^ self.boxes[to]
+    }
+
+    // Hash of a whole state, `player` being the normalized player position.
+    pub(crate) fn state(&self, boxes_hash: u64, player: Pos) -> u64 {
+        boxes_hash ^ self.players[player]
+    }
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use common::{LEVELS, STUCK, UNSOLVABLE, assert_valid_solution};
+use sokoban_solver::{Algorithm, Heuristic, Level, Limit, SolveOutcome, SolverConfig, solve_with};
+
+const ALGORITHMS: &[Algorithm] = &[
+    Algorithm::AStar,
+    Algorithm::IdaStar,
+    Algorithm::GreedyBestFirst,
+    Algorithm::Reverse,
+    Algorithm::Bidirectional,
+    Algorithm::ParallelAStar,
+];
+
+// Whether `Heuristic::Hungarian` makes the algorithm return the fewest pushes.
+fn is_push_optimal(algorithm: Algorithm) -> bool {
+    match algorithm {
+        Algorithm::AStar | Algorithm::IdaStar | Algorithm::Reverse | Algorithm::ParallelAStar => {
+            true
+        }
+        Algorithm::GreedyBestFirst | Algorithm::Bidirectional => false,
+    }
+}
+
+fn config(algorithm: Algorithm) -> SolverConfig {
+    SolverConfig::new().algorithm(algorithm).threads(4)
+}
+
+#[test]
+fn test_every_algorithm_solves() {
+    for &algorithm in ALGORITHMS {
+        for level in LEVELS {
+            let solution = solve_with(&Level::parse(level).unwrap(), &config(algorithm))
+                .into_solution()
+                .unwrap_or_else(|| panic!("{:?} found no solution", algorithm));
+            assert_valid_solution(level, &solution.path);
+        }
+    }
+}
+
+// The box on the left is walled in on its goal, no side of it is left for the player.
+#[test]
+fn test_every_algorithm_solves_around_a_sealed_box() {
+    let level = &["#########", "#*#@ $ .#", "#########"];
+    for &algorithm in ALGORITHMS {
+        let solution = solve_with(&Level::parse(level).unwrap(), &config(algorithm))
+            .into_solution()
+            .unwrap_or_else(|| panic!("{:?} found no solution", algorithm));
+        assert_valid_solution(level, &solution.path);
+    }
+}
+
+#[test]
+fn test_optimal_algorithms_match_astar_with_hungarian() {
+    for level in LEVELS {
+        let level = Level::parse(level).unwrap();
+        let astar = SolverConfig::new().heuristic(Heuristic::Hungarian);
+        let expected = solve_with(&level, &astar).into_solution().unwrap().pushes;
+
+        for &algorithm in ALGORITHMS.iter().filter(|&&a| is_push_optimal(a)) {
+            let config = config(algorithm).heuristic(Heuristic::Hungarian);
+            let pushes = solve_with(&level, &config).into_solution().unwrap().pushes;
+            assert_eq!(pushes, expected, "{:?}", algorithm);
+        }
+    }
+}
+
+#[test]
+fn test_every_algorithm_proves_unsolvable() {
+    let stuck = Level::parse(STUCK).unwrap();
+    for &algorithm in ALGORITHMS {
+        let outcome = solve_with(&stuck, &config(algorithm));
+        assert!(
+            matches!(outcome, SolveOutcome::Unsolvable { .. }),
+            "{:?} returned {:?}",
+            algorithm,
+            outcome
+        );
+    }
+}
+
+// Every algorithm has to run out of states, well before the node limit.
+#[test]
+fn test_every_algorithm_exhausts_an_unsolvable_level() {
+    let level = Level::parse(UNSOLVABLE).unwrap();
+    for &algorithm in ALGORITHMS {
+        let outcome = solve_with(&level, &config(algorithm).max_nodes(10_000));
+        assert!(
+            matches!(outcome, SolveOutcome::Unsolvable { .. }),
+            "{:?} returned {:?}",
+            algorithm,
+            outcome
+        );
+    }
+}
+
+#[test]
+fn test_every_algorithm_stops_at_the_node_limit() {
+    let level = Level::parse(LEVELS[3]).unwrap();
+    for &algorithm in ALGORITHMS {
+        let outcome = solve_with(&level, &config(algorithm).max_nodes(3));
+        assert!(
+            matches!(
+                outcome,
+                SolveOutcome::LimitReached {
+                    limit: Limit::Nodes,
+                    ..
+                }
+            ),
+            "{:?} returned {:?}",
+            algorithm,
+            outcome
+        );
+    }
+}
@@ -0,0 +1,13 @@
+mod common;
+
+use common::{MICROBAN92, assert_valid_solution};
+use sokoban_solver::{Algorithm, Level, SolverConfig, solve_with};
+
+#[test]
+fn test_bidirectional_solves_microban92() {
+    let config = SolverConfig::new().algorithm(Algorithm::Bidirectional);
+    let solution = solve_with(&Level::parse(MICROBAN92).unwrap(), &config)
+        .into_solution()
+        .expect("No solution found");
+    assert_valid_solution(MICROBAN92, &solution.path);
+}
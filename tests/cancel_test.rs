@@ -0,0 +1,35 @@
+mod common;
+
+use std::thread;
+use std::time::Duration;
+
+use common::MICROBAN92;
+use sokoban_solver::{CancelToken, Level, SolveOutcome, SolverConfig, solve_with};
+
+// Microban #92 runs for minutes, so it is still searching when the token fires.
+#[test]
+fn test_cancel_from_another_thread() {
+    let level = Level::parse(MICROBAN92).unwrap();
+    let token = CancelToken::new();
+    let config = SolverConfig::new().cancel_token(token.clone());
+
+    let search = thread::spawn(move || solve_with(&level, &config));
+    thread::sleep(Duration::from_millis(50));
+    token.cancel();
+
+    match search.join().unwrap() {
+        SolveOutcome::Cancelled { stats } => assert!(stats.nodes_expanded > 0),
+        other => panic!("Expected a cancelled search, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_cancelled_before_start() {
+    let level = Level::parse(MICROBAN92).unwrap();
+    let token = CancelToken::new();
+    token.cancel();
+
+    let outcome = solve_with(&level, &SolverConfig::new().cancel_token(token));
+    assert!(matches!(outcome, SolveOutcome::Cancelled { .. }));
+    assert_eq!(outcome.stats().nodes_expanded, 1);
+}
@@ -0,0 +1,89 @@
+// Every test file pulls in the whole module but only uses its own share of it.
+#![allow(dead_code)]
+
+// Small solvable levels every algorithm gets through in well under a second.
+pub const LEVELS: &[&[&str]] = &[
+    &["#####", "#@$.#", "#####"],
+    &["#####", "#   #", "#.$.#", "# $ #", "#+$ #", "#####"],
+    &[
+        "#####", "#   ###", "# $@  #", "##* . #", "# * # #", "#     #", "#######",
+    ],
+    &[
+        "########", "#    . #", "# $  $@#", "#.$.####", "#  #####", "#  #####", "#  #####",
+        "########",
+    ],
+];
+
+// The box is stuck in the corner, the search space runs out right away.
+pub const STUCK: &[&str] = &["#####", "#$  #", "#  .#", "# @ #", "#####"];
+
+// Unsolvable, but no deadlock shows at the root and the pushes can be undone for a while.
+pub const UNSOLVABLE: &[&str] = &[
+    "########", "#. $$. #", "# $    #", "#   $  #", "#  #   #", "# .    #", "# .  @ #", "# #    #",
+    "########",
+];
+
+// Microban #92: minutes for forward A*, quick for the bidirectional search.
+pub const MICROBAN92: &[&str] = &[
+    " #########",
+    "##   #   ##",
+    "#    #    #",
+    "#  $ # $  #",
+    "#   *.*   #",
+    "####.@.####",
+    "#   *.*   #",
+    "#  $ # $  #",
+    "#    #    #",
+    "##   #   ##",
+    " #########",
+];
+
+// Replays a LURD path on the level and checks that it is legal and ends with every box on a goal.
+pub fn assert_valid_solution(level: &[&str], path: &str) {
+    let mut rows: Vec<Vec<u8>> = level.iter().map(|row| row.as_bytes().to_vec()).collect();
+    let cell = |rows: &Vec<Vec<u8>>, r: usize, c: usize| rows[r].get(c).copied().unwrap_or(b' ');
+    let has_box = |ch: u8| ch == b'$' || ch == b'*';
+    let is_goal = |ch: u8| ch == b'.' || ch == b'*' || ch == b'+';
+
+    let (mut pr, mut pc) = rows
+        .iter()
+        .enumerate()
+        .find_map(|(r, row)| {
+            row.iter()
+                .position(|&ch| ch == b'@' || ch == b'+')
+                .map(|c| (r, c))
+        })
+        .expect("Level has no player");
+
+    for (i, step) in path.bytes().enumerate() {
+        let (dr, dc) = match step.to_ascii_lowercase() {
+            b'u' => (-1, 0),
+            b'd' => (1, 0),
+            b'l' => (0, -1),
+            b'r' => (0, 1),
+            _ => panic!("Unknown move {:?} at {}", step as char, i),
+        };
+        let (nr, nc) = ((pr as isize + dr) as usize, (pc as isize + dc) as usize);
+        let next = cell(&rows, nr, nc);
+        assert_ne!(next, b'#', "Player walks into a wall at move {}", i);
+
+        if step.is_ascii_uppercase() {
+            assert!(has_box(next), "Push without a box at move {}", i);
+            let (br, bc) = ((nr as isize + dr) as usize, (nc as isize + dc) as usize);
+            let beyond = cell(&rows, br, bc);
+            assert!(
+                beyond != b'#' && !has_box(beyond),
+                "Blocked push at move {}",
+                i
+            );
+            rows[br][bc] = if is_goal(beyond) { b'*' } else { b'$' };
+            rows[nr][nc] = if is_goal(next) { b'.' } else { b' ' };
+        } else {
+            assert!(!has_box(next), "Player walks into a box at move {}", i);
+        }
+        (pr, pc) = (nr, nc);
+    }
+
+    let unsolved = rows.iter().flatten().filter(|&&ch| ch == b'$').count();
+    assert_eq!(unsolved, 0, "{} boxes are not on a goal", unsolved);
+}
@@ -0,0 +1,163 @@
+mod common;
+
+use common::assert_valid_solution;
+use sokoban_solver::{Level, SolveOutcome, SolverConfig, solve_with};
+
+// Each level allows a single push, which freezes the pushed box against one that is frozen
+// by it in turn. No 2x2 block forms, so only the freeze check can tell.
+#[test]
+fn test_freeze_deadlocks() {
+    let levels: &[&[&str]] = &[
+        // Z shape: walls on opposite sides of two stacked boxes.
+        &[
+            "#######", "#..   #", "## $# #", "###   #", "###$###", "###@###", "#######",
+        ],
+        // The upper box sits on its goal but still freezes the pushed one next to it.
+        &[
+            "#######", "#.    #", "## *# #", "###   #", "###$###", "###@###", "#######",
+        ],
+    ];
+
+    for level in levels {
+        let outcome = solve_with(&Level::parse(level).unwrap(), &SolverConfig::new());
+        let SolveOutcome::Unsolvable { stats } = outcome else {
+            panic!("Expected unsolvable, got {:?}", outcome);
+        };
+        assert_eq!(stats.nodes_expanded, 1);
+        assert_eq!(stats.deadlocks.frozen, 1);
+    }
+}
+
+// The box pushed onto the goal is stuck against the walls and pins the box next to it, which
+// is off its goal. The wall beside the pushed box already blocks that axis.
+#[test]
+fn test_freeze_through_a_box_on_a_goal() {
+    let level = &["######", "#.$ .#", "#$####", "#@#   ", "###   "];
+    let outcome = solve_with(&Level::parse(level).unwrap(), &SolverConfig::new());
+    let SolveOutcome::Unsolvable { stats } = outcome else {
+        panic!("Expected unsolvable, got {:?}", outcome);
+    };
+    assert_eq!(stats.nodes_expanded, 1);
+    assert_eq!(stats.deadlocks.frozen, 1);
+}
+
+#[test]
+fn test_frozen_boxes_on_goals() {
+    let level = &[
+        "#######", "#     #", "## *# #", "###.  #", "###$###", "###@###", "#######",
+    ];
+    let solution = solve_with(&Level::parse(level).unwrap(), &SolverConfig::new())
+        .into_solution()
+        .expect("No solution found");
+    assert_eq!(solution.path, "U");
+}
+
+// The pushed box ends up between walls next to another box, which can still step aside.
+#[test]
+fn test_blocked_by_a_movable_box() {
+    let level = &[
+        "#########",
+        "#  .#####",
+        "# .$ $@##",
+        "#   # # #",
+        "#       #",
+        "#########",
+    ];
+    let solution = solve_with(&Level::parse(level).unwrap(), &SolverConfig::new())
+        .into_solution()
+        .expect("No solution found");
+    assert_valid_solution(level, &solution.path);
+    assert_eq!(solution.pushes, 4);
+}
+
+// The top-left box can only be pushed into the pocket above it, the player never gets behind
+// it. Without the corral check the search would go on pushing the other box around.
+#[test]
+fn test_corral_deadlock() {
+    let level = &[
+        "#########",
+        "# #    .#",
+        "#$      #",
+        "#@  $  .#",
+        "#########",
+    ];
+    let outcome = solve_with(&Level::parse(level).unwrap(), &SolverConfig::new());
+    let SolveOutcome::Unsolvable { stats } = outcome else {
+        panic!("Expected unsolvable, got {:?}", outcome);
+    };
+    assert_eq!(stats.nodes_expanded, 1);
+    assert_eq!(stats.deadlocks.corral, 1);
+}
+
+#[test]
+fn test_corral_with_a_goal_inside() {
+    let level = &[
+        "#########",
+        "#.#     #",
+        "#$      #",
+        "#@  $  .#",
+        "#########",
+    ];
+    let solution = solve_with(&Level::parse(level).unwrap(), &SolverConfig::new())
+        .into_solution()
+        .expect("No solution found");
+    assert_valid_solution(level, &solution.path);
+    assert_eq!(solution.pushes, 4);
+}
+
+// The two boxes along the top wall are frozen on their goals and cut the last goal off from
+// the left. Each box can still reach some goal on its own, so the matching heuristic sees no
+// problem.
+#[test]
+fn test_goal_cut_off_by_frozen_boxes() {
+    let level = &[
+        "#########",
+        "#    **.#",
+        "#  $   ##",
+        "#   @   #",
+        "#########",
+    ];
+    let outcome = solve_with(&Level::parse(level).unwrap(), &SolverConfig::new());
+    let SolveOutcome::Unsolvable { stats } = outcome else {
+        panic!("Expected unsolvable, got {:?}", outcome);
+    };
+    assert_eq!(stats.nodes_expanded, 1);
+    assert!(stats.deadlocks.bipartite > 0);
+    assert_eq!(stats.deadlocks.unmatched, 0);
+
+    // Opened from below, the last goal can be reached around the frozen boxes.
+    let level = &[
+        "#########",
+        "#    **.#",
+        "#  $    #",
+        "#   @   #",
+        "#########",
+    ];
+    let solution = solve_with(&Level::parse(level).unwrap(), &SolverConfig::new())
+        .into_solution()
+        .expect("No solution found");
+    assert_valid_solution(level, &solution.path);
+}
+
+// The top wall has two goals, so a third box pushed up against it can never come back.
+#[test]
+fn test_overfilled_wall_segment() {
+    let level = &["########", "#. .   #", "# $$$  #", "#   @ .#", "########"];
+    let outcome = solve_with(&Level::parse(level).unwrap(), &SolverConfig::new());
+    assert!(outcome.stats().deadlocks.segment > 0);
+    let solution = outcome.into_solution().expect("No solution found");
+    assert_valid_solution(level, &solution.path);
+}
+
+// Pushing the upper box right shuts the player out of the dead corner between the two boxes,
+// and from then on either box could only be pushed into that corner.
+#[test]
+fn test_closed_diagonal() {
+    let level = &[
+        "########", "#.    .#", "#@$ #  #", "# $ #  #", "# ###  #", "#      #", "########",
+    ];
+    let outcome = solve_with(&Level::parse(level).unwrap(), &SolverConfig::new());
+    assert!(outcome.stats().deadlocks.diagonal > 0);
+    let solution = outcome.into_solution().expect("No solution found");
+    assert_valid_solution(level, &solution.path);
+}
@@ -0,0 +1,38 @@
+use sokoban_solver::{Heuristic, Level, SolverConfig, solve_with};
+
+fn pushes(level: &[&str], heuristic: Heuristic) -> usize {
+    let level = Level::parse(level).expect("Valid level");
+    let config = SolverConfig::new().heuristic(heuristic);
+    solve_with(&level, &config)
+        .into_solution()
+        .expect("No solution found")
+        .pushes
+}
+
+#[test]
+fn test_hungarian_finds_fewer_pushes() {
+    let level = &[
+        "#####", "#   ###", "# $@  #", "##* . #", "# * # #", "#     #", "#######",
+    ];
+
+    assert_eq!(pushes(level, Heuristic::Greedy), 9);
+    assert_eq!(pushes(level, Heuristic::Hungarian), 7);
+}
+
+#[test]
+fn test_hungarian_never_worse_than_greedy() {
+    let levels: &[&[&str]] = &[
+        &["#####", "#   #", "#.$.#", "# $ #", "#+$ #", "#####"],
+        &[
+            "########", "#    . #", "# $  $@#", "#.$.####", "#  #####", "#  #####", "#  #####",
+            "########",
+        ],
+        &[
+            "#######", "# . * #", "#.*$ .#", "# $ $ #", "#*$ .*#", "#@* * #", "#######",
+        ],
+    ];
+
+    for level in levels {
+        assert!(pushes(level, Heuristic::Hungarian) <= pushes(level, Heuristic::Greedy));
+    }
+}
@@ -0,0 +1,162 @@
+use sokoban_solver::{Level, LevelError, solve_level, try_solve};
+
+#[test]
+fn test_empty_level() {
+    assert_eq!(Level::parse(&[]).err(), Some(LevelError::Empty));
+    assert_eq!(Level::parse(&["", ""]).err(), Some(LevelError::Empty));
+}
+
+#[test]
+fn test_larger_than_127() {
+    let wall = "#".repeat(200);
+    let start = format!("#@$.{}#", " ".repeat(195));
+    let room = format!("#{}#", " ".repeat(198));
+
+    let mut level = vec![wall.as_str(), start.as_str()];
+    level.extend(std::iter::repeat_n(room.as_str(), 147));
+    level.push(wall.as_str());
+
+    let level = Level::parse(&level).expect("Valid level");
+    assert_eq!((level.width(), level.height()), (200, 150));
+
+    let solution = solve_level(&level).expect("No solution found");
+    assert_eq!(solution.path, "R");
+    assert_eq!(solution.boxes, vec![(1, 3)]);
+}
+
+#[test]
+fn test_no_player() {
+    let level = &["#####", "# $.#", "#####"];
+    assert_eq!(Level::parse(level).err(), Some(LevelError::NoPlayer));
+}
+
+#[test]
+fn test_multiple_players() {
+    let level = &["######", "#@$.@#", "######"];
+    let err = Level::parse(level).err();
+    assert_eq!(err, Some(LevelError::MultiplePlayers { count: 2 }));
+}
+
+#[test]
+fn test_box_goal_mismatch() {
+    let level = &["######", "#@$$.#", "######"];
+    let err = Level::parse(level).err();
+    assert_eq!(
+        err,
+        Some(LevelError::BoxGoalMismatch { boxes: 2, goals: 1 })
+    );
+}
+
+#[test]
+fn test_unknown_char() {
+    let level = &["#####", "#@$x#", "#  .#", "#####"];
+    let err = Level::parse(level).err();
+    assert_eq!(
+        err,
+        Some(LevelError::UnknownChar {
+            row: 1,
+            col: 3,
+            ch: 'x'
+        })
+    );
+}
+
+#[test]
+fn test_try_solve() {
+    assert_eq!(
+        try_solve(&["#####", "#@$.#", "#####"])
+            .unwrap()
+            .unwrap()
+            .path,
+        "R"
+    );
+    assert!(try_solve(&["#####", "# $.#", "#####"]).is_err());
+}
+
+#[test]
+fn test_level_accessors() {
+    let level: Level = "
+####
+# .#
+#  ###
+#*@  #
+#  $ #
+#  ###
+####
+"
+    .parse()
+    .expect("Valid level");
+
+    assert_eq!((level.width(), level.height()), (6, 7));
+    assert_eq!(level.player(), (3, 2));
+    assert_eq!(level.boxes().collect::<Vec<_>>(), vec![(3, 1), (4, 3)]);
+    assert_eq!(level.goals().collect::<Vec<_>>(), vec![(1, 2), (3, 1)]);
+    assert!(level.is_wall(0, 0) && !level.is_floor(0, 0));
+    assert!(level.is_floor(4, 1) && !level.is_wall(4, 1));
+    assert_eq!(level.walls().count() + level.floor().count(), 6 * 7);
+    assert_eq!(level.to_string().lines().nth(3), Some("#*@  #"));
+}
+
+#[test]
+fn test_level_reused_across_solves() {
+    let level = Level::parse(&["#####", "#   #", "#.$.#", "# $ #", "#+$ #", "#####"]).unwrap();
+    let expected = "uuurrdddLruuullddRluurrdLddrU";
+
+    assert_eq!(solve_level(&level).unwrap().path, expected);
+    assert_eq!(solve_level(&level).unwrap().path, expected);
+}
+
+#[test]
+fn test_ragged_rows() {
+    let level = Level::parse(&["  ####", "###  #", "#@$. #", "######"]).expect("Valid level");
+
+    assert!(level.is_exterior(0, 0) && level.is_wall(0, 0));
+    assert!(!level.is_floor(0, 1));
+    assert!(level.is_floor(1, 3));
+    assert_eq!(level.to_string().lines().next(), Some("  ####"));
+    assert_eq!(solve_level(&level).unwrap().path, "R");
+}
+
+#[test]
+fn test_not_enclosed() {
+    // The floor touches the top edge.
+    let open_top = Level::parse(&["#@$.#", "#####"]).err();
+    assert_eq!(open_top, Some(LevelError::NotEnclosed { row: 0, col: 1 }));
+
+    // The short middle row leaves a gap on the right side.
+    let open_side = Level::parse(&["#####", "#@$.", "#####"]).err();
+    assert_eq!(open_side, Some(LevelError::NotEnclosed { row: 1, col: 1 }));
+}
+
+#[test]
+fn test_collections_parse() {
+    for path in std::fs::read_dir("levels").unwrap() {
+        let content = std::fs::read_to_string(path.unwrap().path()).unwrap();
+        let collection: serde_json::Value = serde_json::from_str(&content).unwrap();
+
+        for level in collection["levels"].as_array().unwrap() {
+            let lines: Vec<&str> = level["lines"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|line| line.as_str().unwrap())
+                .collect();
+            assert!(Level::parse(&lines).is_ok(), "{:?}", lines);
+        }
+    }
+}
+
+#[test]
+fn test_push_distance_needs_player_behind_box() {
+    let level = Level::parse(&[
+        "#######", "###.###", "###   #", "### ###", "##@ ###", "##$.$ #", "#######",
+    ])
+    .expect("Valid level");
+
+    // Pushed up from below the junction, the box reaches the top goal in one push.
+    assert_eq!(level.push_distance(2, 3), Some(1));
+    // From the right arm the player would have to walk through the box to get below it.
+    assert_eq!(level.push_distance(2, 4), None);
+    assert_eq!(level.push_distance(0, 0), None);
+    assert_eq!(level.push_distance(5, 3), Some(0));
+}
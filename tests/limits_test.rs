@@ -0,0 +1,125 @@
+mod common;
+
+use std::time::Duration;
+
+use common::STUCK;
+use sokoban_solver::{Level, Limit, SolveOutcome, SolverConfig, solve_with};
+
+// Boring #2 takes thousands of expansions, plenty to trip every limit.
+const BORING2: &[&str] = &[
+    "#######", "#  .+.#", "#.*.####", "# $ $..#", "# $#$$ #", "#*$ $  #", "#      #", "########",
+];
+
+#[test]
+fn test_node_limit() {
+    let level = Level::parse(BORING2).unwrap();
+    let outcome = solve_with(&level, &SolverConfig::new().max_nodes(100));
+
+    match outcome {
+        SolveOutcome::LimitReached { limit, stats } => {
+            assert_eq!(limit, Limit::Nodes);
+            assert_eq!(stats.nodes_expanded, 100);
+        }
+        other => panic!("Expected the node limit, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_time_limit() {
+    let level = Level::parse(BORING2).unwrap();
+    let outcome = solve_with(&level, &SolverConfig::new().max_duration(Duration::ZERO));
+
+    assert!(matches!(
+        outcome,
+        SolveOutcome::LimitReached {
+            limit: Limit::Time,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_memory_limit() {
+    let level = Level::parse(BORING2).unwrap();
+    let outcome = solve_with(&level, &SolverConfig::new().max_memory_bytes(1));
+
+    assert!(matches!(
+        outcome,
+        SolveOutcome::LimitReached {
+            limit: Limit::Memory,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_unsolvable_is_not_a_limit() {
+    let level = Level::parse(STUCK).unwrap();
+    let outcome = solve_with(&level, &SolverConfig::new().max_nodes(1_000_000));
+
+    assert!(matches!(outcome, SolveOutcome::Unsolvable { .. }));
+}
+
+#[test]
+fn test_generous_limits_still_solve() {
+    let level = Level::parse(&["#####", "#   #", "#.$.#", "# $ #", "#+$ #", "#####"]).unwrap();
+    let config = SolverConfig::new()
+        .max_nodes(1_000_000)
+        .max_duration(Duration::from_secs(60))
+        .max_memory_bytes(1 << 30);
+
+    let solution = solve_with(&level, &config).into_solution().unwrap();
+    assert_eq!(solution.path, "uuurrdddLruuullddRluurrdLddrU");
+}
+
+#[test]
+fn test_search_stats() {
+    let level = Level::parse(BORING2).unwrap();
+    let solution = solve_with(&level, &SolverConfig::new())
+        .into_solution()
+        .unwrap();
+    let stats = &solution.stats;
+
+    // Every expanded state except the start was generated first.
+    assert!(stats.nodes_generated + 1 >= stats.nodes_expanded);
+    assert!(stats.duplicates_pruned > 0);
+    assert!(stats.deadlocks.dead_square > 0);
+    assert!(stats.deadlocks.total() >= stats.deadlocks.dead_square);
+    assert!(stats.peak_open > 0 && stats.peak_open <= stats.nodes_generated);
+    assert!(stats.peak_visited > 0);
+}
+
+#[test]
+fn test_transposition_table() {
+    let level = Level::parse(BORING2).unwrap();
+    let exact = solve_with(&level, &SolverConfig::new())
+        .into_solution()
+        .unwrap();
+
+    // Boring #2 fits in one MiB, the bounded table finds the same solution.
+    let config = SolverConfig::new().transposition_table_mb(1);
+    let solution = solve_with(&level, &config).into_solution().unwrap();
+    assert_eq!(solution.path, exact.path);
+
+    // A table of a single bucket keeps overwriting its entries.
+    let config = SolverConfig::new()
+        .transposition_table_mb(0)
+        .max_nodes(1000);
+    let stats = solve_with(&level, &config).stats().clone();
+    assert!(stats.evictions > 0);
+    assert!(stats.peak_visited <= 4);
+}
+
+#[test]
+fn test_full_open_list_is_a_memory_limit() {
+    let level = Level::parse(BORING2).unwrap();
+    let outcome = solve_with(&level, &SolverConfig::new().open_list_mb(0));
+
+    match outcome {
+        SolveOutcome::LimitReached { limit, stats } => {
+            assert_eq!(limit, Limit::Memory);
+            assert!(stats.open_pruned > 0);
+        }
+        other => panic!("Expected the memory limit, got {:?}", other),
+    }
+}
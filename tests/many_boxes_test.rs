@@ -0,0 +1,34 @@
+use sokoban_solver::solve_detailed;
+
+#[test]
+fn test_twenty_boxes() {
+    let level = &[
+        "########################",
+        "#                      #",
+        "# $$$$$$$$$$$$$$$$$$$$ #",
+        "# .................... #",
+        "#@                     #",
+        "########################",
+    ];
+
+    let solution = solve_detailed(level).expect("No solution found");
+    assert_eq!(solution.pushes, 20);
+    assert_eq!(solution.boxes.len(), 20);
+    assert!(solution.boxes.iter().all(|&(r, _)| r == 3));
+}
+
+#[test]
+fn test_many_boxes_already_on_goals() {
+    let level = &[
+        "##########################",
+        "#************************#",
+        "#************************#",
+        "#************************#",
+        "#@$.                     #",
+        "##########################",
+    ];
+
+    let solution = solve_detailed(level).expect("No solution found");
+    assert_eq!(solution.path, "R");
+    assert_eq!(solution.boxes.len(), 73);
+}
@@ -0,0 +1,48 @@
+use sokoban_solver::{Level, Progress, SearchObserver, SolverConfig, solve_observed};
+
+#[derive(Default)]
+struct Recorder {
+    expanded: usize,
+    bounds: Vec<usize>,
+    progress: Vec<(usize, usize, usize)>, // (nodes, open list, visited set)
+}
+
+impl SearchObserver for Recorder {
+    fn on_expand(&mut self, cost: usize, priority: usize) {
+        assert!(cost <= priority);
+        self.expanded += 1;
+    }
+
+    fn on_new_best_lower_bound(&mut self, bound: usize) {
+        self.bounds.push(bound);
+    }
+
+    fn on_progress(&mut self, progress: &Progress) {
+        let nodes = progress.stats.nodes_expanded;
+        self.progress
+            .push((nodes, progress.open_len, progress.visited_len));
+    }
+}
+
+#[test]
+fn test_observer_sees_the_whole_search() {
+    let level = Level::parse(&[
+        "#######", "#  .+.#", "#.*.####", "# $ $..#", "# $#$$ #", "#*$ $  #", "#      #",
+        "########",
+    ])
+    .unwrap();
+    let config = SolverConfig::new().progress_interval(100);
+    let mut recorder = Recorder::default();
+
+    let outcome = solve_observed(&level, &config, &mut recorder);
+    let nodes = outcome.stats().nodes_expanded;
+
+    assert!(outcome.solution().is_some());
+    assert_eq!(recorder.expanded, nodes);
+    assert!(recorder.bounds.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(recorder.progress.len(), nodes / 100);
+    for (i, &(nodes, open, visited)) in recorder.progress.iter().enumerate() {
+        assert_eq!(nodes, (i + 1) * 100);
+        assert!(open > 0 && visited >= open);
+    }
+}
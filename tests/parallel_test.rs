@@ -0,0 +1,33 @@
+mod common;
+
+use common::{LEVELS, assert_valid_solution};
+use sokoban_solver::{Algorithm, Level, SolverConfig, solve_with};
+
+#[test]
+fn test_parallel_solves_on_one_thread() {
+    let config = SolverConfig::new()
+        .algorithm(Algorithm::ParallelAStar)
+        .threads(1);
+
+    for level in LEVELS {
+        let solution = solve_with(&Level::parse(level).unwrap(), &config)
+            .into_solution()
+            .expect("No solution found");
+        assert_valid_solution(level, &solution.path);
+    }
+}
+
+#[test]
+fn test_parallel_thread_stats() {
+    let config = SolverConfig::new()
+        .algorithm(Algorithm::ParallelAStar)
+        .threads(3);
+    let solution = solve_with(&Level::parse(LEVELS[3]).unwrap(), &config)
+        .into_solution()
+        .unwrap();
+    let stats = &solution.stats;
+
+    assert_eq!(stats.threads.len(), 3);
+    let expanded: usize = stats.threads.iter().map(|t| t.nodes_expanded).sum();
+    assert_eq!(expanded, stats.nodes_expanded);
+}
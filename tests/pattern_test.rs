@@ -0,0 +1,69 @@
+mod common;
+
+use common::assert_valid_solution;
+use sokoban_solver::{Level, PatternDatabase, SolverConfig, solve_with};
+
+#[test]
+fn test_patterns_carry_over_through_a_file() {
+    let rows = &[
+        "  ####", "  #  #", "### .#", "#  * #", "# #@ #", "# $* #", "##   #", " #####",
+    ];
+    let level = Level::parse(rows).unwrap();
+
+    let database = PatternDatabase::new();
+    let config = SolverConfig::new().deadlock_patterns(database.clone());
+    let first = solve_with(&level, &config);
+    assert!(first.stats().deadlocks.corral > 0);
+    assert!(!database.is_empty());
+
+    let path = std::env::temp_dir().join(format!("patterns-{}.json", std::process::id()));
+    database.save(&path).unwrap();
+    let loaded = PatternDatabase::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.len(), database.len());
+
+    // Every dead corral is caught by a pattern before it gets expanded.
+    let second = solve_with(&level, &SolverConfig::new().deadlock_patterns(loaded));
+    let stats = second.stats();
+    assert_eq!(stats.deadlocks.corral, 0);
+    assert!(stats.deadlocks.pattern > 0);
+    assert!(stats.nodes_expanded < first.stats().nodes_expanded);
+
+    let solution = second.solution().expect("No solution found");
+    assert_valid_solution(rows, &solution.path);
+    assert_eq!(solution.pushes, first.solution().unwrap().pushes);
+}
+
+#[test]
+fn test_load_rejects_a_broken_file() {
+    let path = std::env::temp_dir().join(format!("broken-{}.json", std::process::id()));
+    std::fs::write(&path, "{\"levels\": [").unwrap();
+    assert!(PatternDatabase::load(&path).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+// Patterns pointing at walls come from a stale or hand-edited file and are left out.
+#[test]
+fn test_load_skips_patterns_on_walls() {
+    let rows = &[
+        "  ####", "  #  #", "### .#", "#  * #", "# #@ #", "# $* #", "##   #", " #####",
+    ];
+    let file = serde_json::json!({"levels": [{
+        "layout": ["######", "###  #", "### .#", "#  . #", "# #  #", "#  . #", "##   #", "######"],
+        "deadlocks": [
+            {"boxes": [[2, 3], [2, 4]], "player": [0, 0], "corral": [0, 0]},
+            {"boxes": [[0, 1], [5, 2]], "player": [1, 3], "corral": [3, 1]},
+        ],
+    }]});
+    let path = std::env::temp_dir().join(format!("walls-{}.json", std::process::id()));
+    std::fs::write(&path, file.to_string()).unwrap();
+    let loaded = PatternDatabase::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let outcome = solve_with(
+        &Level::parse(rows).unwrap(),
+        &SolverConfig::new().deadlock_patterns(loaded),
+    );
+    let solution = outcome.solution().expect("No solution found");
+    assert_valid_solution(rows, &solution.path);
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use common::{LEVELS, MICROBAN92, STUCK, UNSOLVABLE, assert_valid_solution};
+use sokoban_solver::{
+    Algorithm, CancelToken, Heuristic, Level, Portfolio, SolveOutcome, SolverConfig,
+    solve_portfolio, solve_with,
+};
+
+#[test]
+fn test_portfolio_returns_a_winner() {
+    let portfolio = Portfolio::default();
+
+    for level in LEVELS {
+        let result = solve_portfolio(&Level::parse(level).unwrap(), &portfolio);
+        let winner = result.winner.expect("No winner");
+        assert!(["forward", "greedy", "reverse"].contains(&winner.as_str()));
+        assert_valid_solution(level, &result.outcome.solution().unwrap().path);
+    }
+}
+
+#[test]
+fn test_portfolio_cancels_the_slow_strategies() {
+    let level = Level::parse(MICROBAN92).unwrap();
+    let portfolio = Portfolio::new()
+        .strategy("forward", SolverConfig::new())
+        .strategy(
+            "bidirectional",
+            SolverConfig::new().algorithm(Algorithm::Bidirectional),
+        );
+
+    let result = solve_portfolio(&level, &portfolio);
+    assert_eq!(result.winner.as_deref(), Some("bidirectional"));
+}
+
+#[test]
+fn test_portfolio_best_within_deadline() {
+    let level = Level::parse(LEVELS[3]).unwrap();
+    let optimal = SolverConfig::new().heuristic(Heuristic::Hungarian);
+    let portfolio = Portfolio::new()
+        .strategy(
+            "greedy",
+            SolverConfig::new().algorithm(Algorithm::GreedyBestFirst),
+        )
+        .strategy("optimal", optimal.clone())
+        .best_within(Duration::from_secs(60));
+
+    let expected = solve_with(&level, &optimal).into_solution().unwrap().pushes;
+    let result = solve_portfolio(&level, &portfolio);
+    assert_eq!(result.outcome.solution().unwrap().pushes, expected);
+}
+
+#[test]
+fn test_portfolio_unsolvable() {
+    let stuck = Level::parse(STUCK).unwrap();
+    let result = solve_portfolio(&stuck, &Portfolio::default());
+
+    assert_eq!(result.winner, None);
+    assert!(matches!(result.outcome, SolveOutcome::Unsolvable { .. }));
+}
+
+// IDA* without a table takes minutes to run out of states, A* proves it right away.
+#[test]
+fn test_portfolio_stops_at_the_first_proof() {
+    let level = Level::parse(UNSOLVABLE).unwrap();
+    let portfolio = Portfolio::new()
+        .strategy("forward", SolverConfig::new())
+        .strategy(
+            "ida",
+            SolverConfig::new()
+                .algorithm(Algorithm::IdaStar)
+                .transposition_table_mb(0)
+                .max_duration(Duration::from_secs(60)),
+        );
+
+    let start = Instant::now();
+    let result = solve_portfolio(&level, &portfolio);
+    assert_eq!(result.winner, None);
+    assert!(matches!(result.outcome, SolveOutcome::Unsolvable { .. }));
+    assert!(start.elapsed() < Duration::from_secs(30));
+}
+
+#[test]
+fn test_portfolio_cancelled_from_outside() {
+    let level = Level::parse(MICROBAN92).unwrap();
+    let token = CancelToken::new();
+    let portfolio = Portfolio::new()
+        .strategy("forward", SolverConfig::new())
+        .strategy("ida", SolverConfig::new().algorithm(Algorithm::IdaStar))
+        .cancel_token(token.clone());
+
+    let search = thread::spawn(move || solve_portfolio(&level, &portfolio));
+    thread::sleep(Duration::from_millis(50));
+    token.cancel();
+
+    let result = search.join().unwrap();
+    assert_eq!(result.winner, None);
+    assert!(matches!(result.outcome, SolveOutcome::Cancelled { .. }));
+}
+
+#[test]
+fn test_portfolio_keeps_the_strategy_token() {
+    let level = Level::parse(MICROBAN92).unwrap();
+    let token = CancelToken::new();
+    token.cancel();
+    let portfolio = Portfolio::new().strategy("forward", SolverConfig::new().cancel_token(token));
+
+    let result = solve_portfolio(&level, &portfolio);
+    assert!(matches!(result.outcome, SolveOutcome::Cancelled { .. }));
+    assert_eq!(result.outcome.stats().nodes_expanded, 1);
+}
@@ -1,4 +1,7 @@
-use sokoban_solver::solve;
+mod common;
+
+use common::assert_valid_solution;
+use sokoban_solver::{solve, solve_detailed};
 
 #[test]
 fn test_microban() {
@@ -8,6 +11,7 @@ fn test_microban() {
     let expected = "dlUrrrdLullddrUluRuulDrddrruLdlUU";
 
     let actual = solve(level).expect("No solution found");
+    assert_valid_solution(level, &actual);
     assert_eq!(actual, expected);
 }
 
@@ -17,6 +21,7 @@ fn test_petitesse() {
     let expected = "uuurrdddLruuullddRluurrdLddrU";
 
     let actual = solve(level).expect("No solution found");
+    assert_valid_solution(level, &actual);
     assert_eq!(actual, expected);
 }
 
@@ -28,6 +33,7 @@ fn test_scoria() {
     let expected = "UdrddlUruulllddRdrruuuuulDrddddlluRdrUUULDrddlluluuR";
 
     let actual = solve(level).expect("No solution found");
+    assert_valid_solution(level, &actual);
     assert_eq!(actual, expected);
 }
 
@@ -41,6 +47,7 @@ fn test_autogen() {
         "luuluRurrrddlLrruullldlddrdrrUdlluluururrrddLddlluUddrruuLUdrddlluluuRuRDllddrUddrruuL";
 
     let actual = solve(level).expect("No solution found");
+    assert_valid_solution(level, &actual);
     assert_eq!(actual, expected);
 }
 
@@ -52,6 +59,7 @@ fn test_squared() {
     let expected = "UURURRDLdLU";
 
     let actual = solve(level).expect("No solution found");
+    assert_valid_solution(level, &actual);
     assert_eq!(actual, expected);
 }
 
@@ -61,8 +69,21 @@ fn test_boring2() {
         "#######", "#  .+.#", "#.*.####", "# $ $..#", "# $#$$ #", "#*$ $  #", "#      #",
         "########",
     ];
-    let expected = "lddRRDrddlUdllllUUURldddrruRdllluuurRllddRluurruullDRdDlddrUUUUluRdddlUrrRlldddrruLLuuuuRRllddrUluRddldddrrrruLLLdlUUUrrDulldddrrruruLddlluluuruullDRddddrrruLLdlUUUUluRdddlUrrrDrdLLdlUUU";
+    let expected = "lddRRDrddlUdlluRdlllUUURlddRluurRuullDRdDlddrUUddrruLdrrruLLdllluurUUluRRRlldddlddrUUUUluRRldddddrruLdlUUUUluRdddlUrrRDulldddrrruruLdLLLuurrDrdLLdlUUU";
 
     let actual = solve(level).expect("No solution found");
+    assert_valid_solution(level, &actual);
     assert_eq!(actual, expected);
 }
+
+#[test]
+fn test_solution_details() {
+    let level = &["#####", "#   #", "#.$.#", "# $ #", "#+$ #", "#####"];
+
+    let solution = solve_detailed(level).expect("No solution found");
+    assert_eq!(solution.path, "uuurrdddLruuullddRluurrdLddrU");
+    assert_eq!(solution.pushes, 4);
+    assert_eq!(solution.moves, 29);
+    assert!(solution.stats.nodes_expanded > 0);
+    assert_eq!(solution.boxes, vec![(2, 1), (2, 3), (4, 1)]);
+}